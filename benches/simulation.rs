@@ -0,0 +1,91 @@
+// How many positions a second the rules engine and the bitboard can step
+// through, playing random games out on a standard board.
+//
+// cargo bench --bench simulation
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use starter_snake_rust::bitboard::{BitBoard, MAX_SNAKES};
+use starter_snake_rust::engine::{self, Rules};
+use starter_snake_rust::logic::minimax;
+use starter_snake_rust::Board;
+
+const GAMES: u32 = 2_000;
+const MAX_TURNS: u32 = 200;
+
+fn start(rng: &mut StdRng) -> Board {
+    let ids: Vec<String> = (0..4).map(|i| format!("snake-{}", i)).collect();
+    engine::initial_board(11, 11, &ids, rng)
+}
+
+fn report(name: &str, positions: u64, elapsed: Duration) {
+    println!(
+        "{:<8} {:>10} positions in {:>8.1?}, {:>12.0} a second",
+        name,
+        positions,
+        elapsed,
+        positions as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn engine_games(rules: &Rules, rng: &mut StdRng) -> u64 {
+    let mut positions = 0;
+    for _ in 0..GAMES {
+        let mut board = start(rng);
+        let mut turn = 0;
+        while board.snakes.len() > 1 && turn < MAX_TURNS {
+            let moves: HashMap<String, &str> = board
+                .snakes
+                .iter()
+                .map(|s| {
+                    let options = minimax::candidate_moves(rules.mode, &board, s);
+                    (s.id.clone(), *options.choose(rng).unwrap())
+                })
+                .collect();
+            board = engine::advance(rules, &board, &moves, rng).board;
+            positions += 1;
+            turn += 1;
+        }
+    }
+    positions
+}
+
+fn bitboard_games(rules: &Rules, rng: &mut StdRng) -> u64 {
+    let mut positions = 0;
+    for _ in 0..GAMES {
+        let mut board = BitBoard::from_board(rules, &start(rng)).unwrap();
+        let mut turn = 0;
+        while board.alive().count() > 1 && turn < MAX_TURNS {
+            let mut moves = [0; MAX_SNAKES];
+            for (i, m) in moves.iter_mut().enumerate().take(board.snake_count) {
+                let options: Vec<u8> = board.moves(i).collect();
+                *m = options.choose(rng).copied().unwrap_or(0);
+            }
+            board.advance(&moves);
+            positions += 1;
+            turn += 1;
+        }
+    }
+    positions
+}
+
+fn main() {
+    // no food spawns on a bitboard, so the engine doesn't get any either
+    let rules = Rules {
+        food_spawn_chance: 0,
+        minimum_food: 0,
+        ..Rules::default()
+    };
+
+    let started = Instant::now();
+    let positions = engine_games(&rules, &mut StdRng::seed_from_u64(1));
+    report("engine", positions, started.elapsed());
+
+    let started = Instant::now();
+    let positions = bitboard_games(&rules, &mut StdRng::seed_from_u64(1));
+    report("bitboard", positions, started.elapsed());
+}
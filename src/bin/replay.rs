@@ -0,0 +1,317 @@
+// Runs recorded move requests back through `logic::get_move` and shows where
+// the decision differs from the move that was played, so a strategy change
+// can't quietly alter how we handle positions we already understand.
+//
+// cargo run --release --bin replay -- recordings/
+// cargo run --release --bin replay -- gif recordings/game.jsonl game.gif
+//
+// Takes the JSONL files written under `RECORD_DIR`, or plain `GameState`
+// payloads one per line. Exits with 1 when any decision changed. The `gif`
+// subcommand turns one recording into an animation instead.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+use starter_snake_rust::logic::deadline::Deadline;
+use starter_snake_rust::logic::session;
+use starter_snake_rust::logic::view::BoardView;
+use starter_snake_rust::logic::{self, SnakePersonality, StrategyConfig};
+use starter_snake_rust::recorder::{self, Record};
+use starter_snake_rust::render::{self, terminal};
+
+struct Options {
+    // replaces the personality each move was recorded with
+    strategy: Option<StrategyConfig>,
+    changed_only: bool,
+    // prints the board under every move listed
+    show: bool,
+    // draws every replayed turn here when set
+    render_dir: Option<PathBuf>,
+    paths: Vec<PathBuf>,
+}
+
+struct GifOptions {
+    delay_ms: u32,
+    // the recorded path, mode and move on every frame
+    overlay: bool,
+    recording: PathBuf,
+    out: PathBuf,
+}
+
+#[derive(Default)]
+struct Summary {
+    replayed: u32,
+    changed: u32,
+    // moves we have no recorded decision to compare with
+    unknown: u32,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: replay [--strategy ENTRANT] [--changed] [--show] [--render-dir DIR] FILE|DIR [FILE|DIR...]"
+    );
+    eprintln!("       replay gif [--delay MS] [--no-overlay] RECORDING OUT.gif");
+    eprintln!("entrants are PERSONALITY[:DECISION], e.g. snacky or headhunter:minimax=3");
+    eprintln!("without --strategy each move is replayed with the personality it was recorded with");
+    process::exit(2);
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        strategy: None,
+        changed_only: false,
+        show: false,
+        render_dir: None,
+        paths: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strategy" => {
+                let strategy = args.next().unwrap_or_else(|| usage());
+                match StrategyConfig::from_str(&strategy) {
+                    Ok(config) => options.strategy = Some(config),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        usage()
+                    }
+                }
+            }
+            "--changed" => options.changed_only = true,
+            "--show" => options.show = true,
+            "--render-dir" => {
+                options.render_dir = Some(PathBuf::from(args.next().unwrap_or_else(|| usage())))
+            }
+            "-h" | "--help" => usage(),
+            path => options.paths.push(PathBuf::from(path)),
+        }
+    }
+    if options.paths.is_empty() {
+        usage();
+    }
+    options
+}
+
+fn parse_gif_options() -> GifOptions {
+    let mut delay_ms = 200;
+    let mut overlay = true;
+    let mut paths = Vec::new();
+    let mut args = env::args().skip(2);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--delay" => {
+                delay_ms = args.next().and_then(|v| v.parse().ok()).unwrap_or_else(|| {
+                    eprintln!("--delay expects a number");
+                    usage()
+                })
+            }
+            "--no-overlay" => overlay = false,
+            "-h" | "--help" => usage(),
+            path => paths.push(PathBuf::from(path)),
+        }
+    }
+    if paths.len() != 2 {
+        usage();
+    }
+    let out = paths.pop().unwrap();
+    GifOptions {
+        delay_ms,
+        overlay,
+        recording: paths.pop().unwrap(),
+        out,
+    }
+}
+
+// Directories stand for every .jsonl file directly inside them
+fn input_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(path)
+                .unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path.display(), e);
+                    process::exit(2)
+                })
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension() == Some(OsStr::new("jsonl")))
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+// Every line of `path` that parses, the others are logged as warnings
+fn read_file(path: &Path) -> Vec<Record> {
+    recorder::read_records(path).unwrap_or_else(|e| {
+        eprintln!("{}: {}", path.display(), e);
+        process::exit(2)
+    })
+}
+
+// The personality a move was recorded with, or ours when it wasn't
+fn recorded_personality(record: &Record) -> SnakePersonality {
+    SnakePersonality::from_str(&record.personality).unwrap_or(StrategyConfig::default().personality)
+}
+
+fn replay_file(path: &Path, options: &Options, summary: &mut Summary) {
+    let mut last_game = None;
+    for record in read_file(path) {
+        let state = &record.request;
+        last_game = Some(state.game.clone());
+        if record.event == "end" {
+            logic::end(&state.game, &state.turn, &state.board, &state.you);
+            last_game = None;
+            continue;
+        }
+        if record.event != "move" {
+            continue;
+        }
+
+        let config = match &options.strategy {
+            Some(strategy) => strategy.clone(),
+            None => StrategyConfig::for_personality(recorded_personality(&record)),
+        };
+        let view = BoardView::from_state(state);
+        let decided = logic::get_move(
+            &state.game,
+            &state.turn,
+            &view,
+            &config,
+            &Deadline::unbounded(),
+        );
+        if let Some(dir) = &options.render_dir {
+            let image = render::render(&state.board, state.turn, decided.path.as_deref());
+            if let Err(e) = render::save(&image, dir, &state.game.id, state.turn) {
+                eprintln!("{}: {}", dir.display(), e);
+            }
+        }
+        let new = decided.chosen;
+        summary.replayed += 1;
+        let marker = match record.chosen.as_deref() {
+            Some(old) if old == new => " ",
+            Some(_) => {
+                summary.changed += 1;
+                "*"
+            }
+            None => {
+                summary.unknown += 1;
+                "?"
+            }
+        };
+        if !options.changed_only || marker == "*" {
+            println!(
+                "{} {:<36} {:>4} {:>6} -> {}",
+                marker,
+                state.game.id,
+                state.turn,
+                record.chosen.as_deref().unwrap_or("-"),
+                new
+            );
+            if options.show {
+                // NO_COLOR turns the colours off, as in most terminal tools
+                let colour = env::var_os("NO_COLOR").is_none();
+                println!("{}\n", terminal::render(&state.board, colour));
+            }
+        }
+    }
+    // recordings cut off before /end still shouldn't leak into the next game
+    if let Some(game) = last_game {
+        session::finish(&game);
+    }
+}
+
+// One frame for every move in the recording, and the final board if it was
+// recorded to the end
+fn make_gif(options: &GifOptions) {
+    let records = read_file(&options.recording);
+    let mut frames = Vec::new();
+    for record in &records {
+        let state = &record.request;
+        // the path the move followed, planned again for recordings made before
+        // paths were kept
+        let (path, replanned) = match (&record.path, options.overlay) {
+            (Some(path), true) => (path.clone(), false),
+            (None, true) if record.event == "move" => {
+                // keeps the royale history from one frame to the next
+                session::join(&state.game);
+                let view = BoardView::from_state(state);
+                let config = StrategyConfig::for_personality(recorded_personality(record));
+                (
+                    logic::planned_path(&state.game, &state.turn, &view, &config),
+                    true,
+                )
+            }
+            _ => (None, false),
+        };
+        let caption = match (record.event.as_str(), options.overlay) {
+            ("move", true) => format!(
+                "turn {}  {:?} {}: {}{}",
+                state.turn,
+                recorded_personality(record),
+                record.mode.as_deref().unwrap_or("-"),
+                record.chosen.as_deref().unwrap_or("-"),
+                if replanned { "  (re-planned)" } else { "" }
+            ),
+            ("move", false) => format!("turn {}", state.turn),
+            ("end", _) => format!("turn {}  game over", state.turn),
+            _ => continue,
+        };
+        frames.push(render::render_captioned(
+            &state.board,
+            &caption,
+            path.as_deref(),
+        ));
+    }
+    if let Some(record) = records.first() {
+        session::finish(&record.request.game);
+    }
+    if frames.is_empty() {
+        eprintln!("{}: no moves to draw", options.recording.display());
+        process::exit(2);
+    }
+    if let Err(e) = render::save_gif(&frames, &options.out, options.delay_ms) {
+        eprintln!("{}: {}", options.out.display(), e);
+        process::exit(2);
+    }
+    println!(
+        "{} frames written to {}",
+        frames.len(),
+        options.out.display()
+    );
+}
+
+fn main() {
+    // lines that don't parse are logged as warnings, so show those
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    if env::args().nth(1).as_deref() == Some("gif") {
+        make_gif(&parse_gif_options());
+        return;
+    }
+
+    let options = parse_options();
+    let mut summary = Summary::default();
+    for file in input_files(&options.paths) {
+        replay_file(&file, &options, &mut summary);
+    }
+
+    let compared = summary.replayed - summary.unknown;
+    println!(
+        "{} moves replayed, {} of {} recorded decisions changed",
+        summary.replayed, summary.changed, compared
+    );
+    if summary.unknown > 0 {
+        println!("{} moves had no recorded decision", summary.unknown);
+    }
+    if summary.changed > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,318 @@
+// Plays complete games offline between different strategies by running every snake
+// through `logic::get_move` and stepping the board with the local rules engine.
+//
+// cargo run --release --bin tournament -- --games 100 headhunter snacky
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde_json::json;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+
+use starter_snake_rust::engine::{self, Rules};
+use starter_snake_rust::logic::deadline::Deadline;
+use starter_snake_rust::logic::mcts::Rollout;
+use starter_snake_rust::logic::session;
+use starter_snake_rust::logic::view::BoardView;
+use starter_snake_rust::logic::{self, DecisionSource, StrategyConfig};
+use starter_snake_rust::{Board, Game};
+
+struct Options {
+    games: u32,
+    width: u32,
+    height: u32,
+    max_turns: u32,
+    seed: Option<u64>,
+    ruleset: String,
+    entrants: Vec<StrategyConfig>,
+}
+
+#[derive(Default)]
+struct Stats {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    total_length: u32,
+    total_turns: u32,
+    crashes: u32,
+    // how many times each panic message came up
+    crash_reports: BTreeMap<String, u32>,
+}
+
+// Panics on the decision threads, waiting to be put down to whoever was moving
+static CRASH_REPORTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+enum Outcome {
+    Win(usize),
+    Draw(Vec<usize>),
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: tournament [--games N] [--width W] [--height H] [--max-turns T] [--seed S] [--ruleset NAME] \
+         ENTRANT ENTRANT [ENTRANT...]"
+    );
+    eprintln!("entrants are PERSONALITY[:DECISION], e.g. snacky or headhunter:minimax=3");
+    eprintln!("personalities: hungry, timid, headhunter, snacky");
+    eprintln!("decisions: astar (default), minimax[=DEPTH], mcts[=MS|PLAYOUTSp[,ROLLOUT]]");
+    process::exit(2);
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        games: 100,
+        width: 11,
+        height: 11,
+        max_turns: 1000,
+        seed: None,
+        ruleset: "standard".to_string(),
+        entrants: Vec::new(),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut number = |name: &str| -> u64 {
+            args.next().and_then(|v| v.parse().ok()).unwrap_or_else(|| {
+                eprintln!("{} expects a number", name);
+                usage()
+            })
+        };
+        match arg.as_str() {
+            "--games" => options.games = number("--games") as u32,
+            "--width" => options.width = number("--width") as u32,
+            "--height" => options.height = number("--height") as u32,
+            "--max-turns" => options.max_turns = number("--max-turns") as u32,
+            "--seed" => options.seed = Some(number("--seed")),
+            "--ruleset" => options.ruleset = args.next().unwrap_or_else(|| usage()),
+            "-h" | "--help" => usage(),
+            entrant => match StrategyConfig::from_str(entrant) {
+                Ok(config) => options.entrants.push(config),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    usage()
+                }
+            },
+        }
+    }
+    if options.entrants.len() < 2 {
+        usage();
+    }
+    if options.width < engine::MIN_BOARD_SIZE || options.height < engine::MIN_BOARD_SIZE {
+        eprintln!("boards need to be at least {0}x{0}", engine::MIN_BOARD_SIZE);
+        usage();
+    }
+    if options.entrants.len() > engine::MAX_STARTING_SNAKES {
+        eprintln!(
+            "at most {} entrants fit on the board",
+            engine::MAX_STARTING_SNAKES
+        );
+        usage();
+    }
+    // seeded entrants make their random choices repeatably too, so a seed
+    // replays the whole tournament as long as MCTS has a playout budget
+    for entrant in &mut options.entrants {
+        entrant.seed = options.seed;
+    }
+    options
+}
+
+fn describe(config: &StrategyConfig) -> String {
+    match config.decision {
+        DecisionSource::Pathfinding => format!("{:?}", config.personality),
+        DecisionSource::Minimax { depth, .. } => {
+            format!("{:?}:minimax={}", config.personality, depth)
+        }
+        DecisionSource::Mcts { budget, rollout } => {
+            let rollout = match rollout {
+                Rollout::Random => "random".to_string(),
+                Rollout::Personality(p) => format!("{:?}", p),
+            };
+            format!("{:?}:mcts={},{}", config.personality, budget, rollout)
+        }
+    }
+}
+
+fn play_game(
+    options: &Options,
+    game_number: u32,
+    rng: &mut StdRng,
+    stats: &mut [Stats],
+) -> Outcome {
+    let ids: Vec<String> = (0..options.entrants.len())
+        .map(|i| format!("snake-{}", i))
+        .collect();
+    let game = Game {
+        id: format!("tournament-{}", game_number),
+        ruleset: HashMap::from([
+            ("name".to_string(), json!(options.ruleset)),
+            ("version".to_string(), json!("local")),
+        ]),
+        timeout: 500,
+    };
+    let rules = Rules::from_game(&game);
+    let mut board: Board = engine::initial_board(options.width, options.height, &ids, rng);
+    let mut final_lengths = vec![0; ids.len()];
+    let mut turns_survived = vec![0; ids.len()];
+
+    let mut turn = 0;
+    while board.snakes.len() > 1 && turn < options.max_turns {
+        let mut moves = HashMap::new();
+        for snake in &board.snakes {
+            let idx = ids.iter().position(|id| id == &snake.id).unwrap();
+            let config = &options.entrants[idx];
+            CRASH_REPORTS.lock().unwrap().clear();
+            let decided = panic::catch_unwind(AssertUnwindSafe(|| {
+                logic::get_move(
+                    &game,
+                    &turn,
+                    &BoardView::new(&board, snake),
+                    config,
+                    &Deadline::unbounded(),
+                )
+            }));
+            match decided {
+                Ok(decided) => {
+                    moves.insert(snake.id.clone(), decided.chosen);
+                }
+                // The official engine keeps a snake going straight when it fails to
+                // answer, so a crash costs it a move rather than the game
+                Err(cause) => {
+                    let report = CRASH_REPORTS
+                        .lock()
+                        .unwrap()
+                        .pop()
+                        .unwrap_or_else(|| panic_message(&*cause));
+                    stats[idx].crashes += 1;
+                    *stats[idx].crash_reports.entry(report).or_insert(0) += 1;
+                }
+            }
+        }
+        let result = engine::advance(&rules, &board, &moves, rng);
+        turn += 1;
+        for (id, _) in &result.eliminated {
+            let idx = ids.iter().position(|i| i == id).unwrap();
+            turns_survived[idx] = turn;
+        }
+        for snake in &board.snakes {
+            let idx = ids.iter().position(|i| i == &snake.id).unwrap();
+            final_lengths[idx] = snake.length;
+        }
+        board = result.board;
+    }
+    // nobody sends /end here, so let go of the game ourselves
+    session::finish(&game);
+
+    for snake in &board.snakes {
+        let idx = ids.iter().position(|i| i == &snake.id).unwrap();
+        final_lengths[idx] = snake.length;
+        turns_survived[idx] = turn;
+    }
+    for (idx, s) in stats.iter_mut().enumerate() {
+        s.total_length += final_lengths[idx];
+        s.total_turns += turns_survived[idx];
+    }
+
+    let alive: Vec<usize> = board
+        .snakes
+        .iter()
+        .map(|s| ids.iter().position(|i| i == &s.id).unwrap())
+        .collect();
+    if alive.len() == 1 {
+        return Outcome::Win(alive[0]);
+    }
+    if alive.is_empty() {
+        // everybody went out on the same turn
+        let last: Vec<usize> = (0..ids.len())
+            .filter(|&i| turns_survived[i] == turn)
+            .collect();
+        return Outcome::Draw(last);
+    }
+    Outcome::Draw(alive)
+}
+
+fn panic_message(cause: &(dyn Any + Send)) -> String {
+    match cause.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match cause.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "unknown panic".to_string(),
+        },
+    }
+}
+
+fn main() {
+    env_logger::init();
+    // a panicking decision is counted as a crash and reported at the end instead
+    // of flooding the output. Panics anywhere else are real bugs and still shown.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() == Some(logic::DECISION_THREAD) {
+            CRASH_REPORTS.lock().unwrap().push(info.to_string());
+        } else {
+            default_hook(info);
+        }
+    }));
+
+    let options = parse_options();
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut stats: Vec<Stats> = options.entrants.iter().map(|_| Stats::default()).collect();
+
+    for game_number in 0..options.games {
+        match play_game(&options, game_number, &mut rng, &mut stats) {
+            Outcome::Win(winner) => {
+                for (idx, s) in stats.iter_mut().enumerate() {
+                    if idx == winner {
+                        s.wins += 1;
+                    } else {
+                        s.losses += 1;
+                    }
+                }
+            }
+            Outcome::Draw(drawn) => {
+                for (idx, s) in stats.iter_mut().enumerate() {
+                    if drawn.contains(&idx) {
+                        s.draws += 1;
+                    } else {
+                        s.losses += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let games = options.games.max(1) as f64;
+    println!(
+        "{} {} games on {}x{}",
+        options.games, options.ruleset, options.width, options.height
+    );
+    println!(
+        "{:<4} {:<24} {:>7} {:>7} {:>7} {:>8} {:>9} {:>8}",
+        "#", "strategy", "win%", "draw%", "loss%", "avg len", "avg turns", "crashes"
+    );
+    for (idx, (config, s)) in options.entrants.iter().zip(stats.iter()).enumerate() {
+        println!(
+            "{:<4} {:<24} {:>7.1} {:>7.1} {:>7.1} {:>8.1} {:>9.1} {:>8}",
+            idx,
+            describe(config),
+            100.0 * s.wins as f64 / games,
+            100.0 * s.draws as f64 / games,
+            100.0 * s.losses as f64 / games,
+            s.total_length as f64 / games,
+            s.total_turns as f64 / games,
+            s.crashes
+        );
+    }
+    for (config, s) in options.entrants.iter().zip(stats.iter()) {
+        for (report, count) in &s.crash_reports {
+            println!("{} crashed {}x: {}", describe(config), count, report);
+        }
+    }
+}
@@ -0,0 +1,430 @@
+// A compact board for search. Everything lives in fixed size arrays so a board
+// is Copy and cloning it is a memcpy, and a turn is stepped without touching the
+// heap.
+//
+// Bodies are stored as links: every body cell records the direction of the
+// next segment towards the head, so moving a snake only touches its head and
+// tail. Growth is kept as a count of segments stacked on the tail.
+//
+// Stepping follows `engine::advance` except that no food is spawned, which is
+// what searches want anyway. `benches/simulation.rs` compares the two.
+
+use crate::engine::{GameMode, Rules, MAX_HEALTH, MOVES};
+use crate::{Board, Coord};
+
+// Enough for the largest 25x25 boards
+pub const MAX_CELLS: usize = 640;
+pub const MAX_SNAKES: usize = 8;
+const WORDS: usize = MAX_CELLS / 64;
+
+// Directions are indexes into `engine::MOVES`
+const UP: u8 = 0;
+const DOWN: u8 = 1;
+const LEFT: u8 = 2;
+const RIGHT: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BitSet([u64; WORDS]);
+
+impl BitSet {
+    pub fn set(&mut self, cell: u16) {
+        self.0[cell as usize / 64] |= 1 << (cell % 64);
+    }
+
+    pub fn clear(&mut self, cell: u16) {
+        self.0[cell as usize / 64] &= !(1 << (cell % 64));
+    }
+
+    pub fn contains(&self, cell: u16) -> bool {
+        self.0[cell as usize / 64] & (1 << (cell % 64)) != 0
+    }
+
+    pub fn count(&self) -> u32 {
+        self.0.iter().map(|w| w.count_ones()).sum()
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..MAX_CELLS as u16).filter(move |c| self.contains(*c))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BitSnake {
+    pub alive: bool,
+    pub health: u8,
+    pub length: u16,
+    pub head: u16,
+    // the cell behind the head, equal to the head while the whole body is
+    // stacked at the start of a game
+    pub neck: u16,
+    pub tail: u16,
+    // segments sitting on top of the tail, which stays put while there are any
+    pub stacked: u16,
+}
+
+#[derive(Clone, Copy)]
+pub struct BitBoard {
+    pub width: u8,
+    pub height: u8,
+    pub wrapped: bool,
+    pub constrictor: bool,
+    pub hazard_damage: u8,
+    pub food: BitSet,
+    // how many hazards are stacked on each cell
+    pub hazards: [u8; MAX_CELLS],
+    // every body segment except the heads
+    pub bodies: BitSet,
+    pub snakes: [BitSnake; MAX_SNAKES],
+    pub snake_count: usize,
+    links: [u8; MAX_CELLS],
+}
+
+impl BitBoard {
+    // None if the board is too big or has too many snakes to fit
+    pub fn from_board(rules: &Rules, board: &Board) -> Option<BitBoard> {
+        if (board.width * board.height) as usize > MAX_CELLS || board.snakes.len() > MAX_SNAKES {
+            return None;
+        }
+        let mut bits = BitBoard {
+            width: board.width as u8,
+            height: board.height as u8,
+            wrapped: rules.mode.wraps(),
+            constrictor: rules.mode == GameMode::Constrictor,
+            hazard_damage: rules.hazard_damage_per_turn.min(u8::MAX as u32) as u8,
+            food: BitSet::default(),
+            hazards: [0; MAX_CELLS],
+            bodies: BitSet::default(),
+            snakes: [BitSnake::default(); MAX_SNAKES],
+            snake_count: board.snakes.len(),
+            links: [0; MAX_CELLS],
+        };
+        for f in &board.food {
+            bits.food.set(bits.cell(f));
+        }
+        for h in &board.hazards {
+            let cell = bits.cell(h) as usize;
+            bits.hazards[cell] = bits.hazards[cell].saturating_add(1);
+        }
+        for (i, snake) in board.snakes.iter().enumerate() {
+            let cells: Vec<u16> = snake.body.iter().map(|c| bits.cell(c)).collect();
+            let mut stacked = 0;
+            for pair in cells.windows(2) {
+                let (toward_head, segment) = (pair[0], pair[1]);
+                if segment == toward_head {
+                    stacked += 1;
+                    continue;
+                }
+                bits.links[segment as usize] = bits.direction(segment, toward_head)?;
+                bits.bodies.set(segment);
+            }
+            bits.snakes[i] = BitSnake {
+                alive: true,
+                health: snake.health.min(MAX_HEALTH) as u8,
+                length: cells.len() as u16,
+                head: cells[0],
+                neck: cells
+                    .iter()
+                    .copied()
+                    .find(|c| *c != cells[0])
+                    .unwrap_or(cells[0]),
+                tail: cells[cells.len() - 1],
+                stacked,
+            };
+        }
+        Some(bits)
+    }
+
+    // Writes the snakes, food and hazards back over a copy of the board this
+    // was built from. Snakes that have died are dropped.
+    pub fn to_board(&self, original: &Board) -> Board {
+        let mut board = original.clone();
+        board.food = self.food.cells().map(|c| self.coord(c)).collect();
+        board.hazards = (0..MAX_CELLS as u16)
+            .flat_map(|c| (0..self.hazards[c as usize]).map(move |_| c))
+            .map(|c| self.coord(c))
+            .collect();
+        board.snakes = original
+            .snakes
+            .iter()
+            .zip(self.snakes.iter())
+            .filter(|(_, bits)| bits.alive)
+            .map(|(snake, bits)| {
+                // walk from the tail up to the head, then flip it round
+                let mut body = Vec::new();
+                let mut cell = bits.tail;
+                loop {
+                    body.push(self.coord(cell));
+                    if cell == bits.head {
+                        break;
+                    }
+                    cell = match self.neighbour(cell, self.links[cell as usize]) {
+                        Some(next) => next,
+                        None => bits.head,
+                    };
+                }
+                body.reverse();
+                let tail = self.coord(bits.tail);
+                body.extend((0..bits.stacked).map(|_| tail.clone()));
+                let mut snake = snake.clone();
+                snake.head = body[0].clone();
+                snake.length = body.len() as u32;
+                snake.body = body;
+                snake.health = bits.health as u32;
+                snake
+            })
+            .collect();
+        board
+    }
+
+    pub fn cell(&self, c: &Coord) -> u16 {
+        (c.y * self.width as u32 + c.x) as u16
+    }
+
+    pub fn coord(&self, cell: u16) -> Coord {
+        Coord {
+            x: (cell % self.width as u16) as u32,
+            y: (cell / self.width as u16) as u32,
+        }
+    }
+
+    // The cell one step away in `direction`, or None off the edge of the board
+    pub fn neighbour(&self, cell: u16, direction: u8) -> Option<u16> {
+        let (w, h) = (self.width as i32, self.height as i32);
+        let (x, y) = (
+            (cell % self.width as u16) as i32,
+            (cell / self.width as u16) as i32,
+        );
+        let (x, y) = match direction {
+            UP => (x, y + 1),
+            DOWN => (x, y - 1),
+            LEFT => (x - 1, y),
+            RIGHT => (x + 1, y),
+            _ => return None,
+        };
+        if self.wrapped {
+            return Some((y.rem_euclid(h) * w + x.rem_euclid(w)) as u16);
+        }
+        if x < 0 || y < 0 || x >= w || y >= h {
+            return None;
+        }
+        Some((y * w + x) as u16)
+    }
+
+    fn direction(&self, from: u16, to: u16) -> Option<u8> {
+        (0..MOVES.len() as u8).find(|d| self.neighbour(from, *d) == Some(to))
+    }
+
+    pub fn alive(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.snake_count).filter(move |i| self.snakes[*i].alive)
+    }
+
+    // Directions that stay on the board and don't turn back into the neck
+    pub fn moves(&self, snake: usize) -> impl Iterator<Item = u8> + '_ {
+        let s = self.snakes[snake];
+        (0..MOVES.len() as u8)
+            .filter(move |d| matches!(self.neighbour(s.head, *d), Some(c) if c != s.neck || s.neck == s.head))
+    }
+
+    // Steps every snake one turn. `moves` holds a direction per snake, in the
+    // same order as `snakes`; dead snakes' entries are ignored.
+    pub fn advance(&mut self, moves: &[u8]) {
+        let mut out_of_bounds = [false; MAX_SNAKES];
+
+        // 1. move
+        for i in 0..self.snake_count {
+            let s = self.snakes[i];
+            if !s.alive {
+                continue;
+            }
+            let head = match self.neighbour(s.head, moves[i]) {
+                Some(head) => head,
+                None => {
+                    out_of_bounds[i] = true;
+                    continue;
+                }
+            };
+            let mut s = s;
+            if s.stacked > 0 {
+                s.stacked -= 1;
+            } else if s.tail == s.head {
+                s.tail = head;
+            } else {
+                self.bodies.clear(s.tail);
+                s.tail = self
+                    .neighbour(s.tail, self.links[s.tail as usize])
+                    .unwrap_or(s.head);
+            }
+            if s.length > 1 {
+                self.links[s.head as usize] = moves[i];
+                self.bodies.set(s.head);
+            }
+            s.neck = s.head;
+            s.head = head;
+            self.snakes[i] = s;
+        }
+
+        // 2, 3 and 4. health, hazards and feeding
+        for (s, gone) in self.snakes.iter_mut().zip(out_of_bounds.iter()) {
+            if !s.alive || *gone {
+                continue;
+            }
+            s.health = s.health.saturating_sub(1);
+            let fed = self.food.contains(s.head);
+            if !fed {
+                let damage = self.hazard_damage as u32 * self.hazards[s.head as usize] as u32;
+                s.health = s.health.saturating_sub(damage.min(u8::MAX as u32) as u8);
+            }
+            if fed || self.constrictor {
+                s.health = MAX_HEALTH as u8;
+                s.stacked += 1;
+                s.length += 1;
+            }
+        }
+        if self.constrictor {
+            self.food = BitSet::default();
+        }
+        for i in 0..self.snake_count {
+            if self.snakes[i].alive {
+                self.food.clear(self.snakes[i].head);
+            }
+        }
+
+        // 5. eliminate, starting with snakes that left the board or starved so
+        // nobody collides with them
+        let mut dead = [false; MAX_SNAKES];
+        for (i, gone) in out_of_bounds.iter().enumerate().take(self.snake_count) {
+            if self.snakes[i].alive && (*gone || self.snakes[i].health == 0) {
+                self.remove(i);
+            }
+        }
+        for i in self.alive() {
+            let s = self.snakes[i];
+            if self.bodies.contains(s.head) {
+                dead[i] = true;
+                continue;
+            }
+            dead[i] = self.alive().any(|j| {
+                let other = self.snakes[j];
+                j != i && other.head == s.head && other.length >= s.length
+            });
+        }
+        for (i, d) in dead.iter().enumerate() {
+            if *d {
+                self.remove(i);
+            }
+        }
+    }
+
+    fn remove(&mut self, snake: usize) {
+        let s = self.snakes[snake];
+        let mut cell = s.tail;
+        while cell != s.head {
+            self.bodies.clear(cell);
+            cell = match self.neighbour(cell, self.links[cell as usize]) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        self.snakes[snake].alive = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::mock::StepRng;
+    use std::collections::HashMap;
+
+    use super::BitBoard;
+    use crate::engine::{self, GameMode, Rules, MOVES};
+    use crate::test_support::snake;
+    use crate::{Board, Coord};
+
+    // what we compare: everything about a snake that the rules change
+    fn summary(board: &Board) -> Vec<(String, Vec<Coord>, u32)> {
+        board
+            .snakes
+            .iter()
+            .map(|s| (s.id.clone(), s.body.clone(), s.health))
+            .collect()
+    }
+
+    // Steps `start` through `turns` with both the engine and a bitboard,
+    // checking they agree after every turn, and returns the engine's board
+    fn step_both(rules: &Rules, start: &Board, turns: &[[&str; 3]]) -> Board {
+        let mut bits = BitBoard::from_board(rules, start).unwrap();
+        assert_eq!(summary(&bits.to_board(start)), summary(start));
+        let mut board = start.clone();
+        let mut rng = StepRng::new(0, 1);
+        for turn in turns.iter() {
+            let moves: HashMap<String, &str> = start
+                .snakes
+                .iter()
+                .zip(turn.iter())
+                .map(|(s, m)| (s.id.clone(), *m))
+                .collect();
+            let directions: Vec<u8> = turn
+                .iter()
+                .map(|m| MOVES.iter().position(|d| d == m).unwrap() as u8)
+                .collect();
+            board = engine::advance(rules, &board, &moves, &mut rng).board;
+            bits.advance(&directions);
+            let stepped = bits.to_board(start);
+            assert_eq!(summary(&stepped), summary(&board));
+            assert_eq!(stepped.food, board.food);
+        }
+        board
+    }
+
+    #[test]
+    fn steps_like_the_rules_engine() {
+        let rules = Rules {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            ..Rules::default()
+        };
+        let start = Board {
+            width: 7,
+            height: 7,
+            food: vec![Coord { x: 1, y: 3 }, Coord { x: 5, y: 5 }],
+            hazards: vec![
+                Coord { x: 3, y: 3 },
+                Coord { x: 3, y: 5 },
+                Coord { x: 3, y: 5 },
+            ],
+            snakes: vec![
+                snake("a", vec![(1, 1), (1, 1), (1, 1)]),
+                snake("b", vec![(4, 3), (4, 2), (4, 1), (5, 1)]),
+                snake("c", vec![(5, 4), (6, 4), (6, 3)]),
+            ],
+        };
+        // b runs through the hazard, c runs into b's body, a eats and then
+        // runs into b's neck, and b ends up two hazards deep
+        let turns = [
+            ["up", "left", "down"],
+            ["up", "left", "left"],
+            ["right", "up", "left"],
+            ["up", "up", "up"],
+            ["up", "right", "up"],
+        ];
+        let board = step_both(&rules, &start, &turns);
+        assert!(board.snakes.iter().all(|s| s.id == "b"));
+        assert_eq!(board.snakes[0].health, 90 - 5 - 14 - 2 * 14);
+
+        // in constrictor a eats on the first turn and still only grows once a
+        // turn, and the food it didn't eat is gone too
+        let constrictor = Rules {
+            mode: GameMode::Constrictor,
+            ..rules
+        };
+        let start = Board {
+            food: vec![Coord { x: 1, y: 2 }, Coord { x: 5, y: 5 }],
+            ..start
+        };
+        let turns = [["up", "left", "down"], ["up", "left", "left"]];
+        let board = step_both(&constrictor, &start, &turns);
+        assert!(board.food.is_empty());
+        assert!(board.snakes.iter().all(|s| s.length == s.body.len() as u32));
+        let a = board.snakes.iter().find(|s| s.id == "a").unwrap();
+        assert_eq!(a.length, 5);
+    }
+}
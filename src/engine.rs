@@ -0,0 +1,520 @@
+// A local copy of the standard Battlesnake rules, so a board can be stepped forward
+// without a live game. Mirrors the turn order of the official rules engine:
+//   1. move every snake
+//   2. reduce health
+//   3. apply hazard damage
+//   4. feed snakes
+//   5. spawn food
+//   6. eliminate snakes
+// See https://docs.battlesnake.com/guides/game/rules
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::utils;
+use crate::{Battlesnake, Board, Coord, Game};
+
+pub const MAX_HEALTH: u32 = 100;
+pub const MOVES: [&str; 4] = ["up", "down", "left", "right"];
+
+// The game modes we play differently in, from `game.ruleset.name`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameMode {
+    Standard,
+    Wrapped,
+    Constrictor,
+    Royale,
+}
+
+impl GameMode {
+    pub fn from_game(game: &Game) -> GameMode {
+        match game.ruleset.get("name").and_then(Value::as_str) {
+            Some("wrapped") => GameMode::Wrapped,
+            Some("constrictor") => GameMode::Constrictor,
+            Some("royale") => GameMode::Royale,
+            _ => GameMode::Standard,
+        }
+    }
+
+    pub fn wraps(&self) -> bool {
+        *self == GameMode::Wrapped
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rules {
+    pub mode: GameMode,
+    pub food_spawn_chance: u32,
+    pub minimum_food: u32,
+    pub hazard_damage_per_turn: u32,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            mode: GameMode::Standard,
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+        }
+    }
+}
+
+impl Rules {
+    // Reads the rule settings sent in `game.ruleset.settings`, falling back to the
+    // standard defaults for anything missing
+    pub fn from_game(game: &Game) -> Rules {
+        let defaults = Rules::default();
+        let settings = game.ruleset.get("settings");
+        let setting = |name: &str, default: u32| {
+            settings
+                .and_then(|s| s.get(name))
+                .and_then(Value::as_u64)
+                .map(|v| v as u32)
+                .unwrap_or(default)
+        };
+        Rules {
+            mode: GameMode::from_game(game),
+            food_spawn_chance: setting("foodSpawnChance", defaults.food_spawn_chance),
+            minimum_food: setting("minimumFood", defaults.minimum_food),
+            hazard_damage_per_turn: setting("hazardDamagePerTurn", defaults.hazard_damage_per_turn),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EliminationCause {
+    OutOfBounds,
+    OutOfHealth,
+    SelfCollision,
+    BodyCollision(String),
+    HeadToHead(String),
+}
+
+#[derive(Debug)]
+pub struct TurnResult {
+    pub board: Board,
+    pub eliminated: Vec<(String, EliminationCause)>,
+}
+
+// Returns the coordinate one step from `coord` in `direction`, or None if that
+// would leave the board. In wrapped games the board has no edges.
+pub fn step(mode: GameMode, board: &Board, coord: &Coord, direction: &str) -> Option<Coord> {
+    let (x, y) = (coord.x as i64, coord.y as i64);
+    let (x, y) = match direction {
+        "up" => (x, y + 1),
+        "down" => (x, y - 1),
+        "left" => (x - 1, y),
+        "right" => (x + 1, y),
+        _ => return None,
+    };
+    if mode.wraps() {
+        return Some(Coord {
+            x: x.rem_euclid(board.width as i64) as u32,
+            y: y.rem_euclid(board.height as i64) as u32,
+        });
+    }
+    if x < 0 || y < 0 || x >= board.width as i64 || y >= board.height as i64 {
+        return None;
+    }
+    Some(Coord {
+        x: x as u32,
+        y: y as u32,
+    })
+}
+
+// The direction the snake moved last turn, which the official engine repeats
+// for snakes that don't answer in time. In wrapped games the neck can be on the
+// far edge from the head.
+pub fn current_direction(snake: &Battlesnake) -> &'static str {
+    match (snake.body.first(), snake.body.get(1)) {
+        (Some(head), Some(neck)) if head != neck => utils::get_next_move_from_coord(neck, head),
+        _ => "up",
+    }
+}
+
+// Advances the board by one turn. `moves` maps snake ids to "up", "down", "left"
+// or "right"; snakes without an entry keep going in their current direction.
+// Eliminated snakes are removed from the returned board.
+pub fn advance<R: Rng>(
+    rules: &Rules,
+    board: &Board,
+    moves: &HashMap<String, &str>,
+    rng: &mut R,
+) -> TurnResult {
+    let mut next = board.clone();
+    let mut eliminated = Vec::new();
+
+    // 1. move
+    let mut moved = Vec::new();
+    for snake in next.snakes.drain(..) {
+        let direction = moves
+            .get(&snake.id)
+            .copied()
+            .unwrap_or_else(|| current_direction(&snake));
+        match step(rules.mode, board, &snake.head, direction) {
+            Some(head) => {
+                let mut snake = snake;
+                snake.body.insert(0, head.clone());
+                snake.body.pop();
+                snake.head = head;
+                moved.push(snake);
+            }
+            None => eliminated.push((snake.id, EliminationCause::OutOfBounds)),
+        }
+    }
+    next.snakes = moved;
+
+    // 2. health
+    for snake in next.snakes.iter_mut() {
+        snake.health = snake.health.saturating_sub(1);
+    }
+
+    // 3. hazards, once for every hazard stacked on the cell
+    for snake in next.snakes.iter_mut() {
+        if next.food.contains(&snake.head) {
+            continue;
+        }
+        let stacked = next.hazards.iter().filter(|h| **h == snake.head).count() as u32;
+        snake.health = snake
+            .health
+            .saturating_sub(rules.hazard_damage_per_turn * stacked);
+    }
+
+    // 4. feed. In constrictor every snake grows every turn and never goes
+    // hungry, so food means nothing and is cleared away.
+    let constrictor = rules.mode == GameMode::Constrictor;
+    for snake in next.snakes.iter_mut() {
+        if constrictor || next.food.contains(&snake.head) {
+            snake.health = MAX_HEALTH;
+            let tail = snake.body.last().cloned().unwrap_or_default();
+            snake.body.push(tail);
+        }
+        snake.length = snake.body.len() as u32;
+    }
+    if constrictor {
+        next.food.clear();
+    } else {
+        let heads: Vec<Coord> = next.snakes.iter().map(|s| s.head.clone()).collect();
+        next.food.retain(|f| !heads.contains(f));
+    }
+
+    // 5. spawn food
+    spawn_food(rules, &mut next, rng);
+
+    // 6. eliminate
+    let mut casualties = Vec::new();
+    for snake in &next.snakes {
+        if snake.health == 0 {
+            casualties.push((snake.id.clone(), EliminationCause::OutOfHealth));
+        }
+    }
+    let starved: Vec<String> = casualties.iter().map(|c| c.0.clone()).collect();
+    let survivors: Vec<&Battlesnake> = next
+        .snakes
+        .iter()
+        .filter(|s| !starved.contains(&s.id))
+        .collect();
+    for snake in &survivors {
+        if let Some(cause) = collision(snake, &survivors) {
+            casualties.push((snake.id.clone(), cause));
+        }
+    }
+    let dead: Vec<String> = casualties.iter().map(|c| c.0.clone()).collect();
+    next.snakes.retain(|s| !dead.contains(&s.id));
+    eliminated.extend(casualties);
+
+    TurnResult {
+        board: next,
+        eliminated,
+    }
+}
+
+// The smallest board whose starting corners and edge midpoints are all
+// different cells
+pub const MIN_BOARD_SIZE: u32 = 5;
+
+// One for each corner and edge midpoint
+pub const MAX_STARTING_SNAKES: usize = 8;
+
+// Lays out a fresh board the way the official engine does: snakes start stacked on
+// the corner (then edge midpoint) positions, each gets a piece of food on the
+// diagonal towards the centre, and one more piece goes in the middle
+pub fn initial_board<R: Rng>(width: u32, height: u32, ids: &[String], rng: &mut R) -> Board {
+    assert!(
+        width >= MIN_BOARD_SIZE && height >= MIN_BOARD_SIZE,
+        "Boards need to be at least {0}x{0}",
+        MIN_BOARD_SIZE
+    );
+    let (mn_x, md_x, mx_x) = (1, (width - 1) / 2, width - 2);
+    let (mn_y, md_y, mx_y) = (1, (height - 1) / 2, height - 2);
+    let mut corners = vec![(mn_x, mn_y), (mn_x, mx_y), (mx_x, mn_y), (mx_x, mx_y)];
+    let mut cardinals = vec![(mn_x, md_y), (md_x, mn_y), (md_x, mx_y), (mx_x, md_y)];
+    corners.shuffle(rng);
+    cardinals.shuffle(rng);
+    let starts: Vec<(u32, u32)> = corners.into_iter().chain(cardinals).collect();
+    assert!(ids.len() <= starts.len(), "Too many snakes for this board");
+
+    let snakes: Vec<Battlesnake> = ids
+        .iter()
+        .zip(starts.iter())
+        .map(|(id, &(x, y))| Battlesnake {
+            id: id.clone(),
+            name: id.clone(),
+            health: MAX_HEALTH,
+            body: vec![Coord { x, y }; 3],
+            head: Coord { x, y },
+            length: 3,
+            latency: "0".to_string(),
+            shout: None,
+        })
+        .collect();
+
+    let mut board = Board {
+        height,
+        width,
+        food: Vec::new(),
+        snakes,
+        hazards: Vec::new(),
+    };
+    let centre = Coord { x: md_x, y: md_y };
+    for i in 0..board.snakes.len() {
+        let head = board.snakes[i].head.clone();
+        let towards_centre = |a: u32, c: u32| if a < c { a + 1 } else { a - 1 };
+        let food = Coord {
+            x: towards_centre(head.x, centre.x),
+            y: towards_centre(head.y, centre.y),
+        };
+        // small boards put some starts right on another snake's diagonal
+        let on_a_snake = board.snakes.iter().any(|s| s.head == food);
+        if food != centre && !on_a_snake && !board.food.contains(&food) {
+            board.food.push(food);
+        }
+    }
+    if !board.snakes.iter().any(|s| s.head == centre) {
+        board.food.push(centre);
+    }
+    board
+}
+
+fn collision(snake: &Battlesnake, others: &[&Battlesnake]) -> Option<EliminationCause> {
+    if snake.body[1..].contains(&snake.head) {
+        return Some(EliminationCause::SelfCollision);
+    }
+    for other in others.iter().filter(|o| o.id != snake.id) {
+        if other.body[1..].contains(&snake.head) {
+            return Some(EliminationCause::BodyCollision(other.id.clone()));
+        }
+    }
+    for other in others.iter().filter(|o| o.id != snake.id) {
+        if other.head == snake.head && other.body.len() >= snake.body.len() {
+            return Some(EliminationCause::HeadToHead(other.id.clone()));
+        }
+    }
+    None
+}
+
+fn spawn_food<R: Rng>(rules: &Rules, board: &mut Board, rng: &mut R) {
+    if rules.mode == GameMode::Constrictor {
+        return;
+    }
+    let count = if (board.food.len() as u32) < rules.minimum_food {
+        rules.minimum_food - board.food.len() as u32
+    } else if rules.food_spawn_chance > 0 && rng.gen_range(0..100) < rules.food_spawn_chance {
+        1
+    } else {
+        0
+    };
+    for _ in 0..count {
+        let free = unoccupied_cells(board);
+        match free.choose(rng) {
+            Some(cell) => board.food.push(cell.clone()),
+            None => return,
+        }
+    }
+}
+
+fn unoccupied_cells(board: &Board) -> Vec<Coord> {
+    let mut free = Vec::new();
+    for x in 0..board.width {
+        for y in 0..board.height {
+            let c = Coord { x, y };
+            if board.food.contains(&c) || board.snakes.iter().any(|s| s.body.contains(&c)) {
+                continue;
+            }
+            free.push(c);
+        }
+    }
+    free
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::mock::StepRng;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+
+    use super::{
+        advance, current_direction, initial_board, EliminationCause, GameMode, Rules,
+        MAX_STARTING_SNAKES, MIN_BOARD_SIZE,
+    };
+    use crate::test_support::snake;
+    use crate::{Board, Coord};
+
+    fn no_spawn() -> Rules {
+        Rules {
+            food_spawn_chance: 0,
+            minimum_food: 0,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn moves_and_feeds() {
+        let board = Board {
+            width: 11,
+            height: 11,
+            food: vec![Coord { x: 2, y: 3 }],
+            hazards: Vec::new(),
+            snakes: vec![snake("a", vec![(2, 2), (2, 1), (2, 0)])],
+        };
+        let moves = HashMap::from([("a".to_string(), "up")]);
+        let result = advance(&no_spawn(), &board, &moves, &mut StepRng::new(0, 1));
+        let a = &result.board.snakes[0];
+        assert_eq!(a.head, Coord { x: 2, y: 3 });
+        assert_eq!(a.health, 100);
+        assert_eq!(a.length, 4);
+        assert!(result.board.food.is_empty());
+    }
+
+    #[test]
+    fn eliminations() {
+        let board = Board {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![
+                snake("wall", vec![(0, 5), (1, 5), (2, 5)]),
+                snake("big", vec![(5, 5), (5, 4), (5, 3), (5, 2)]),
+                snake("small", vec![(7, 5), (7, 4), (7, 3)]),
+            ],
+        };
+        let moves = HashMap::from([
+            ("wall".to_string(), "left"),
+            ("big".to_string(), "right"),
+            ("small".to_string(), "left"),
+        ]);
+        let result = advance(&no_spawn(), &board, &moves, &mut StepRng::new(0, 1));
+        assert_eq!(result.board.snakes.len(), 1);
+        assert_eq!(result.board.snakes[0].id, "big");
+        assert!(result
+            .eliminated
+            .contains(&("wall".to_string(), EliminationCause::OutOfBounds)));
+        assert!(result.eliminated.contains(&(
+            "small".to_string(),
+            EliminationCause::HeadToHead("big".to_string())
+        )));
+    }
+
+    #[test]
+    fn stacked_hazards_hurt_more() {
+        let board = Board {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: vec![
+                Coord { x: 2, y: 3 },
+                Coord { x: 2, y: 3 },
+                Coord { x: 5, y: 6 },
+            ],
+            snakes: vec![
+                snake("stacked", vec![(2, 2), (2, 1), (2, 0)]),
+                snake("single", vec![(5, 5), (5, 4), (5, 3)]),
+            ],
+        };
+        let moves = HashMap::from([("stacked".to_string(), "up"), ("single".to_string(), "up")]);
+        let result = advance(&no_spawn(), &board, &moves, &mut StepRng::new(0, 1));
+        // one for the turn plus 14 for every hazard
+        assert_eq!(result.board.snakes[0].health, 90 - 1 - 28);
+        assert_eq!(result.board.snakes[1].health, 90 - 1 - 14);
+    }
+
+    #[test]
+    fn constrictor_grows_every_turn() {
+        let board = Board {
+            width: 11,
+            height: 11,
+            // eating doesn't make it grow any faster
+            food: vec![Coord { x: 2, y: 3 }, Coord { x: 8, y: 8 }],
+            hazards: Vec::new(),
+            snakes: vec![snake("a", vec![(2, 2), (2, 1), (2, 0)])],
+        };
+        let rules = Rules {
+            mode: GameMode::Constrictor,
+            ..Rules::default()
+        };
+        let moves = HashMap::from([("a".to_string(), "up")]);
+        let result = advance(&rules, &board, &moves, &mut StepRng::new(0, 1));
+        let a = &result.board.snakes[0];
+        assert_eq!(a.length, 4);
+        assert_eq!(a.health, 100);
+        assert_eq!(a.body.last(), Some(&Coord { x: 2, y: 1 }));
+        assert!(result.board.food.is_empty());
+    }
+
+    #[test]
+    fn keeps_going_across_a_wrapped_edge() {
+        // moved right off the right edge and came back in on the left
+        let wrapped = snake("a", vec![(0, 5), (10, 5), (9, 5)]);
+        assert_eq!(current_direction(&wrapped), "right");
+        assert_eq!(
+            current_direction(&snake("b", vec![(5, 10), (5, 0), (5, 1)])),
+            "down"
+        );
+        assert_eq!(
+            current_direction(&snake("c", vec![(5, 6), (5, 5), (5, 4)])),
+            "up"
+        );
+
+        let board = Board {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![wrapped],
+        };
+        let rules = Rules {
+            mode: GameMode::Wrapped,
+            ..no_spawn()
+        };
+        // no answer, so it carries on the way it was going
+        let result = advance(&rules, &board, &HashMap::new(), &mut StepRng::new(0, 1));
+        assert_eq!(result.board.snakes[0].head, Coord { x: 1, y: 5 });
+    }
+
+    #[test]
+    fn starting_food_never_lands_on_a_snake() {
+        let ids: Vec<String> = (0..MAX_STARTING_SNAKES).map(|i| i.to_string()).collect();
+        for seed in 0..20 {
+            let board = initial_board(
+                MIN_BOARD_SIZE,
+                MIN_BOARD_SIZE,
+                &ids,
+                &mut StdRng::seed_from_u64(seed),
+            );
+            assert_eq!(board.snakes.len(), MAX_STARTING_SNAKES);
+            assert!(!board.food.is_empty());
+            for food in &board.food {
+                assert!(
+                    board.snakes.iter().all(|s| s.head != *food),
+                    "food on a snake at {:?}",
+                    food
+                );
+            }
+        }
+    }
+}
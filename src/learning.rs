@@ -42,7 +42,7 @@ impl Agent<MyState> for MyAgent {
     fn current_state(&self) -> &MyState {
         &self.state
     }
-    fn take_action(&mut self, action: &MyAction) -> () {
+    fn take_action(&mut self, action: &MyAction) {
         match action {
             &MyAction { dx, dy } => {
                 self.state = MyState {
@@ -0,0 +1,88 @@
+#[macro_use]
+extern crate lazy_static;
+
+use rocket::serde::Deserialize;
+use rurel::AgentTrainer;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub mod bitboard;
+pub mod engine;
+pub mod learning;
+pub mod logic;
+pub mod recorder;
+pub mod render;
+pub mod utils;
+
+// Fixtures shared by the unit tests
+#[cfg(test)]
+pub(crate) mod test_support {
+    use crate::{Battlesnake, Coord};
+
+    // A snake with 90 health and `body` listed from head to tail
+    pub fn snake(id: &str, body: Vec<(u32, u32)>) -> Battlesnake {
+        let body: Vec<Coord> = body.into_iter().map(|(x, y)| Coord { x, y }).collect();
+        Battlesnake {
+            id: id.to_string(),
+            name: id.to_string(),
+            health: 90,
+            head: body[0].clone(),
+            length: body.len() as u32,
+            body,
+            latency: "".to_string(),
+            shout: None,
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref AGENT_TRAINER: Arc<Mutex<AgentTrainer<learning::MyState>>> =
+        Arc::new(Mutex::from(AgentTrainer::new()));
+}
+
+// API and Response Objects
+// See https://docs.battlesnake.com/api
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Game {
+    pub id: String,
+    pub ruleset: HashMap<String, Value>,
+    pub timeout: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Board {
+    pub height: u32,
+    pub width: u32,
+    pub food: Vec<Coord>,
+    pub snakes: Vec<Battlesnake>,
+    pub hazards: Vec<Coord>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Battlesnake {
+    pub id: String,
+    pub name: String,
+    pub health: u32,
+    pub body: Vec<Coord>,
+    pub head: Coord,
+    pub length: u32,
+    pub latency: String,
+    pub shout: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
+pub struct Coord {
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct GameState {
+    pub game: Game,
+    pub turn: u32,
+    pub board: Board,
+    pub you: Battlesnake,
+}
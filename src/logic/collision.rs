@@ -0,0 +1,109 @@
+// Where enemy heads can go next turn. Meeting a snake at least as long as us
+// head on kills us, so the cells next to their heads are deadly. Next to a
+// shorter snake it's the other way round, and they're the best place to be.
+
+use log::debug;
+
+use crate::engine::{self, GameMode, MOVES};
+use crate::Coord as BattlesnakeCoord;
+
+use super::view::BoardView;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeadRisk {
+    Deadly,
+    Opportunity,
+}
+
+#[derive(Debug, Clone)]
+pub struct CollisionRisk {
+    cells: Vec<(BattlesnakeCoord, HeadRisk)>,
+}
+
+impl CollisionRisk {
+    pub fn from_view(game_mode: GameMode, view: &BoardView) -> CollisionRisk {
+        let mut cells: Vec<(BattlesnakeCoord, HeadRisk)> = Vec::new();
+        for snake in &view.opponents {
+            let risk = if snake.body.len() >= view.you.body.len() {
+                HeadRisk::Deadly
+            } else {
+                HeadRisk::Opportunity
+            };
+            let neck = snake.body.get(1).filter(|n| **n != snake.head);
+            for direction in MOVES {
+                let c = match engine::step(game_mode, view.board, &snake.head, direction) {
+                    Some(c) if Some(&c) != neck => c,
+                    _ => continue,
+                };
+                // a cell two snakes can reach is only as good as the worse of them
+                match cells.iter_mut().find(|(cell, _)| *cell == c) {
+                    Some(existing) => {
+                        if risk == HeadRisk::Deadly {
+                            existing.1 = HeadRisk::Deadly;
+                        }
+                    }
+                    None => cells.push((c, risk)),
+                }
+            }
+        }
+        debug!("Head to head risk: {:?}", cells);
+        CollisionRisk { cells }
+    }
+
+    pub fn risk_at(&self, c: &BattlesnakeCoord) -> Option<HeadRisk> {
+        self.cells
+            .iter()
+            .find(|(cell, _)| cell == c)
+            .map(|(_, risk)| *risk)
+    }
+
+    pub fn is_deadly(&self, c: &BattlesnakeCoord) -> bool {
+        self.risk_at(c) == Some(HeadRisk::Deadly)
+    }
+
+    pub fn opportunities(&self) -> impl Iterator<Item = &BattlesnakeCoord> {
+        self.cells
+            .iter()
+            .filter(|(_, risk)| *risk == HeadRisk::Opportunity)
+            .map(|(cell, _)| cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollisionRisk, HeadRisk};
+    use crate::engine::GameMode;
+    use crate::logic::view::BoardView;
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+
+    #[test]
+    fn marks_cells_next_to_enemy_heads() {
+        let you = snake("you", vec![(5, 5), (5, 4), (5, 3)]);
+        // as long as us, so meeting it head on kills us both
+        let equal = snake("equal", vec![(2, 2), (1, 2), (0, 2)]);
+        let small = snake("small", vec![(8, 8), (8, 9)]);
+        // also next to the equal snake's head at (2, 2)
+        let shared = snake("shared", vec![(3, 3), (4, 3)]);
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), equal, small, shared],
+        };
+        let risk = CollisionRisk::from_view(GameMode::Standard, &BoardView::new(&board, &you));
+        assert!(risk.is_deadly(&BattlesnakeCoord { x: 2, y: 3 }));
+        assert_eq!(
+            risk.risk_at(&BattlesnakeCoord { x: 8, y: 7 }),
+            Some(HeadRisk::Opportunity)
+        );
+        assert!(risk.is_deadly(&BattlesnakeCoord { x: 3, y: 2 }));
+        assert_eq!(
+            risk.risk_at(&BattlesnakeCoord { x: 3, y: 4 }),
+            Some(HeadRisk::Opportunity)
+        );
+        assert_eq!(risk.risk_at(&BattlesnakeCoord { x: 5, y: 6 }), None);
+        assert_eq!(risk.opportunities().count(), 3 + 1);
+    }
+}
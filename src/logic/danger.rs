@@ -0,0 +1,241 @@
+// Extra cost on cells that are risky to path through, so A* prefers open and
+// safe terrain over the merely shortest route. How much each kind of danger
+// matters depends on the personality.
+
+use log::debug;
+
+use crate::engine::GameMode;
+use crate::utils::{self};
+use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
+
+use super::collision::CollisionRisk;
+use super::view::BoardView;
+use super::SnakePersonality;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DangerWeights {
+    // next to the edge of the board
+    pub wall: u32,
+    // a cell a snake at least our size could move into next turn
+    pub enemy_head: u32,
+    // two or fewer ways out
+    pub corridor: u32,
+    // per hazard stacked on the cell, on top of the damage based cost
+    pub hazard: u32,
+}
+
+impl DangerWeights {
+    pub fn for_personality(personality: &SnakePersonality) -> DangerWeights {
+        match *personality {
+            SnakePersonality::Hungry => DangerWeights {
+                wall: 0,
+                enemy_head: 1,
+                corridor: 1,
+                hazard: 0,
+            },
+            SnakePersonality::Timid => DangerWeights {
+                wall: 1,
+                enemy_head: 6,
+                corridor: 3,
+                hazard: 3,
+            },
+            SnakePersonality::HeadHunter => DangerWeights {
+                wall: 1,
+                enemy_head: 2,
+                corridor: 2,
+                hazard: 2,
+            },
+            SnakePersonality::Snacky => DangerWeights {
+                wall: 1,
+                enemy_head: 4,
+                corridor: 2,
+                hazard: 2,
+            },
+        }
+    }
+}
+
+// Adds the weighted dangers to every open cell of `pathfinding_board`, and to
+// the cost of stepping on bodies once they've moved away. Walls stay walls.
+pub fn apply(
+    pathfinding_board: &mut PathfindingBoard,
+    game_mode: GameMode,
+    view: &BoardView,
+    weights: &DangerWeights,
+) {
+    let board = view.board;
+    let risk = CollisionRisk::from_view(game_mode, view);
+    let (width, height) = (
+        pathfinding_board.width as i16,
+        pathfinding_board.height as i16,
+    );
+    let mut extra = Vec::new();
+    for row in 0..height {
+        for col in 0..width {
+            let pos = PathfindingPos(col, row);
+            if pathfinding_board.cost(&pos).is_none() && pathfinding_board.clear_at(&pos).is_none()
+            {
+                continue;
+            }
+            let c = utils::pos_to_coord(board, &pos);
+            let mut danger = 0;
+            let on_edge = col == 0 || row == 0 || col == width - 1 || row == height - 1;
+            if on_edge && !game_mode.wraps() {
+                danger += weights.wall;
+            }
+            if risk.is_deadly(&c) {
+                danger += weights.enemy_head;
+            }
+            if pathfinding_board.get_successors(&pos).len() <= 2 {
+                danger += weights.corridor;
+            }
+            if !board.food.contains(&c) {
+                danger += weights.hazard * board.hazards.iter().filter(|h| **h == c).count() as u32;
+            }
+            if danger > 0 {
+                extra.push((pos, danger));
+            }
+        }
+    }
+    // worked out before changing anything so corridors are judged on the
+    // board as it was
+    for (pos, danger) in &extra {
+        pathfinding_board.add_cost(pos, *danger);
+    }
+    debug!("Danger added to {} cells", extra.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, DangerWeights};
+    use crate::engine::GameMode;
+    use crate::logic::deadline::Deadline;
+    use crate::logic::goal::find_path;
+    use crate::logic::view::BoardView;
+    use crate::logic::SnakePersonality;
+    use crate::test_support::snake;
+    use crate::utils::{build_pathfinding_board_with_hazards, coord_to_pos, FromBattlesnake};
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
+    use rust_pathfinding::Board as PathfindingBoard;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn paths_keep_off_the_walls() {
+        let you = snake("you", vec![(0, 0); 3]);
+        let board = BattlesnakeBoard {
+            width: 7,
+            height: 7,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone()],
+        };
+        let mut pathfinding_board = PathfindingBoard::from_battlesnake(&board);
+        pathfinding_board.set_cost(&coord_to_pos(&board, &you.head), Some(1));
+        let weights = DangerWeights {
+            wall: 5,
+            enemy_head: 0,
+            corridor: 0,
+            hazard: 0,
+        };
+        apply(
+            &mut pathfinding_board,
+            GameMode::Standard,
+            &BoardView::new(&board, &you),
+            &weights,
+        );
+        assert_eq!(pathfinding_board.to_lines()[3], "6111116");
+
+        // from one end of the bottom wall to the other, stepping in a row
+        // costs less than running along the edge
+        let path = find_path(
+            &pathfinding_board,
+            &coord_to_pos(&board, &BattlesnakeCoord { x: 1, y: 0 }),
+            &coord_to_pos(&board, &BattlesnakeCoord { x: 5, y: 0 }),
+            &Deadline::unbounded(),
+        )
+        .unwrap();
+        assert!(path
+            .0
+            .iter()
+            .any(|p| *p == coord_to_pos(&board, &BattlesnakeCoord { x: 3, y: 1 })));
+    }
+
+    #[test]
+    fn corridors_cost_more() {
+        let you = snake("you", vec![(9, 9); 3]);
+        let board = BattlesnakeBoard {
+            width: 5,
+            height: 3,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: Vec::new(),
+        };
+        // cells with two ways out or fewer get the corridor weight
+        let mut pathfinding_board = PathfindingBoard::new(
+            vec![
+                "11111".to_string(),
+                "X1X11".to_string(),
+                "11111".to_string(),
+            ],
+            false,
+        );
+        let weights = DangerWeights {
+            wall: 0,
+            enemy_head: 0,
+            corridor: 4,
+            hazard: 0,
+        };
+        // wrapped, so being on the edge doesn't count for anything either
+        apply(
+            &mut pathfinding_board,
+            GameMode::Wrapped,
+            &BoardView::new(&board, &you),
+            &weights,
+        );
+        assert_eq!(
+            pathfinding_board.to_lines(),
+            vec!["51515", "X5X11", "51515"]
+        );
+    }
+
+    #[test]
+    fn cells_a_bigger_head_can_reach_cost_more() {
+        // our tail is right next to the big snake's head
+        let you = snake("you", vec![(2, 3), (3, 3), (3, 4)]);
+        let big = snake("big", vec![(4, 4), (5, 4), (6, 4), (6, 3)]);
+        let board = BattlesnakeBoard {
+            width: 7,
+            height: 7,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), big],
+        };
+        let game = Game {
+            id: "game".to_string(),
+            ruleset: HashMap::from([("name".to_string(), json!("wrapped"))]),
+            timeout: 500,
+        };
+        let view = BoardView::new(&board, &you);
+        let mut pathfinding_board =
+            build_pathfinding_board_with_hazards(&SnakePersonality::Snacky, &game, &view);
+        let weights = DangerWeights {
+            wall: 0,
+            enemy_head: 5,
+            corridor: 0,
+            hazard: 0,
+        };
+        apply(&mut pathfinding_board, GameMode::Wrapped, &view, &weights);
+        let cost = |x, y| pathfinding_board.cost(&coord_to_pos(&board, &BattlesnakeCoord { x, y }));
+        assert_eq!(cost(4, 5), Some(6));
+        assert_eq!(cost(4, 3), Some(6));
+        assert_eq!(cost(1, 1), Some(1));
+
+        // the danger sticks to bodies for when they've moved out of the way
+        let tail = coord_to_pos(&board, &BattlesnakeCoord { x: 3, y: 4 });
+        assert_eq!(pathfinding_board.cost(&tail), None);
+        assert_eq!(pathfinding_board.clear_at(&tail), Some((1, 6)));
+        let neck = coord_to_pos(&board, &BattlesnakeCoord { x: 3, y: 3 });
+        assert_eq!(pathfinding_board.clear_at(&neck), Some((2, 1)));
+    }
+}
@@ -0,0 +1,119 @@
+// How long we have left to answer a move request. The clock starts when the
+// request comes in and `game.timeout` is cut down by a margin for the time the
+// response spends on the network. Searches check it as they go and give up
+// once it has passed, so a decision we stopped waiting for doesn't keep running.
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use crate::Game;
+
+// Used when `NETWORK_MARGIN_MS` isn't set
+pub const DEFAULT_NETWORK_MARGIN_MS: u64 = 100;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Deadline {
+    started: Instant,
+    budget: Duration,
+}
+
+impl Deadline {
+    pub fn new(started: Instant, timeout: Duration, margin: Duration) -> Deadline {
+        Deadline {
+            started,
+            budget: timeout.saturating_sub(margin),
+        }
+    }
+
+    // The clock for a move in `game` that arrived at `started`, taking the
+    // margin from the `NETWORK_MARGIN_MS` environment variable
+    pub fn since(started: Instant, game: &Game) -> Deadline {
+        let margin = env::var("NETWORK_MARGIN_MS")
+            .ok()
+            .and_then(|m| m.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_NETWORK_MARGIN_MS);
+        Deadline::new(
+            started,
+            Duration::from_millis(game.timeout as u64),
+            Duration::from_millis(margin),
+        )
+    }
+
+    // Starts the clock for a move in `game` now
+    pub fn start(game: &Game) -> Deadline {
+        Deadline::since(Instant::now(), game)
+    }
+
+    // A deadline that never passes, for offline runs that should decide the
+    // same way however fast the machine is
+    pub fn unbounded() -> Deadline {
+        Deadline {
+            started: Instant::now(),
+            budget: Duration::MAX,
+        }
+    }
+
+    // The same clock, ending `reserve` sooner
+    pub fn reserve(&self, reserve: Duration) -> Deadline {
+        Deadline {
+            started: self.started,
+            budget: self.budget.saturating_sub(reserve),
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.budget.saturating_sub(self.started.elapsed())
+    }
+
+    pub fn expired(&self) -> bool {
+        self.started.elapsed() >= self.budget
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Deadline;
+
+    #[test]
+    fn margin_comes_off_the_timeout() {
+        let deadline = Deadline::new(
+            Instant::now(),
+            Duration::from_millis(500),
+            Duration::from_millis(100),
+        );
+        assert!(!deadline.expired());
+        assert!(deadline.remaining() <= Duration::from_millis(400));
+
+        let gone = Deadline::new(
+            Instant::now(),
+            Duration::from_millis(50),
+            Duration::from_millis(100),
+        );
+        assert!(gone.expired());
+        assert_eq!(gone.remaining(), Duration::from_millis(0));
+
+        // time spent before we got to start the clock still counts
+        let late = Deadline::new(
+            Instant::now() - Duration::from_millis(450),
+            Duration::from_millis(500),
+            Duration::from_millis(100),
+        );
+        assert!(late.expired());
+
+        let reserved = Deadline::new(
+            Instant::now(),
+            Duration::from_millis(500),
+            Duration::from_millis(100),
+        )
+        .reserve(Duration::from_millis(350));
+        assert!(reserved.remaining() <= Duration::from_millis(50));
+        assert!(late.reserve(Duration::from_millis(1000)).expired());
+
+        assert!(!Deadline::unbounded().expired());
+        assert!(!Deadline::unbounded()
+            .reserve(Duration::from_millis(20))
+            .expired());
+    }
+}
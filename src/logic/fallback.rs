@@ -0,0 +1,76 @@
+// Moves to fall back on when there is no path to the goal. Each one is a little
+// more desperate than the last, but they all produce an answer.
+
+use crate::engine::{self, GameMode, MOVES};
+use crate::utils::{self};
+use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+use rust_pathfinding::Board as PathfindingBoard;
+
+use super::collision::CollisionRisk;
+use super::deadline::Deadline;
+use super::view::BoardView;
+use super::{goal, minimax};
+
+// Follow our own tail, which is always safe to move towards as long as we
+// haven't just eaten (a stacked tail doesn't move out of the way)
+pub fn chase_tail(
+    pathfinding_board: &PathfindingBoard,
+    view: &BoardView,
+    deadline: &Deadline,
+) -> Option<&'static str> {
+    let (board, you) = (view.board, view.you);
+    if !utils::tail_moves(you) {
+        return None;
+    }
+    let tail = utils::coord_to_pos(board, &you.body[you.body.len() - 1]);
+    let mut open_board = pathfinding_board.clone();
+    open_board.set_cost(&tail, Some(1));
+    let head = utils::coord_to_pos(board, &you.head);
+    let path = goal::find_path(&open_board, &head, &tail, deadline)?;
+    let next = path.0.get(1)?;
+    Some(utils::get_next_move_from_coord(
+        &you.head,
+        &utils::pos_to_coord(board, next),
+    ))
+}
+
+// Anything that keeps us on the board
+pub fn any_in_bounds_move(
+    game_mode: GameMode,
+    board: &BattlesnakeBoard,
+    head: &BattlesnakeCoord,
+) -> Option<&'static str> {
+    MOVES
+        .iter()
+        .find(|m| engine::step(game_mode, board, head, m).is_some())
+        .copied()
+}
+
+// A move worked out before any real thinking, to send if the thinking runs
+// out of time. It stays on the board and out of bodies (tails move out of the
+// way unless they're stacked), and off cells a bigger head could also reach
+// when there's a choice.
+pub fn safe_move(game_mode: GameMode, view: &BoardView) -> &'static str {
+    let occupied = |c: &BattlesnakeCoord| {
+        view.board.snakes.iter().any(|s| {
+            let solid = if utils::tail_moves(s) && game_mode != GameMode::Constrictor {
+                &s.body[..s.body.len() - 1]
+            } else {
+                &s.body[..]
+            };
+            solid.contains(c)
+        })
+    };
+    let risk = CollisionRisk::from_view(game_mode, view);
+    let moves = minimax::candidate_moves(game_mode, view.board, view.you);
+    let open: Vec<(&'static str, BattlesnakeCoord)> = moves
+        .iter()
+        .filter_map(|m| engine::step(game_mode, view.board, &view.you.head, m).map(|c| (*m, c)))
+        .filter(|(_, c)| !occupied(c))
+        .collect();
+    open.iter()
+        .find(|(_, c)| !risk.is_deadly(c))
+        .or_else(|| open.first())
+        .map(|(m, _)| *m)
+        .unwrap_or(moves[0])
+}
@@ -1,127 +1,330 @@
-use core::panic;
-use pathfinding::prelude::astar;
+use log::debug;
+use pathfinding::prelude::{astar, dijkstra_all};
 
+use crate::engine::GameMode;
 use crate::utils::{self};
 use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
 use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
 
-use super::{SnakeMode, SnakePersonality};
+use super::collision::CollisionRisk;
+use super::deadline::Deadline;
+use super::royale::{self, Forecast};
+use super::view::BoardView;
+use super::{space, SnakeMode, SnakePersonality};
 
 pub fn determine_goal(
     personality: &SnakePersonality,
     mode: &SnakeMode,
     pathfinding_board: &PathfindingBoard,
-    board: &BattlesnakeBoard,
-    head: &BattlesnakeCoord,
+    game_mode: GameMode,
+    forecast: Option<&Forecast>,
+    view: &BoardView,
+    deadline: &Deadline,
 ) -> Option<(Vec<PathfindingPos>, u32)> {
+    let (board, head) = (view.board, &view.you.head);
     let self_pos = utils::coord_to_pos(board, head);
-    let goal_pos = match personality {
-        &SnakePersonality::Snacky => utils::coord_to_pos(board, find_delicious_food(board, head)),
-        &SnakePersonality::HeadHunter => {
+    if game_mode == GameMode::Constrictor {
+        // there's no food and bodies never shrink, so the only thing worth
+        // going after is room to keep moving
+        return find_roomiest_step(pathfinding_board, board, head);
+    }
+    let goal = match *personality {
+        SnakePersonality::Hungry => {
+            return find_closest_food_path(pathfinding_board, game_mode, forecast, view, deadline);
+        }
+        SnakePersonality::Snacky => find_delicious_food(game_mode, forecast, view),
+        SnakePersonality::HeadHunter => {
             if *mode == SnakeMode::Eat {
-                utils::coord_to_pos(board, find_delicious_food(board, head))
+                find_delicious_food(game_mode, forecast, view)
+            } else {
+                let target = find_delicious_snake(game_mode, forecast, view)?;
+                return find_path(
+                    pathfinding_board,
+                    &self_pos,
+                    &utils::coord_to_pos(board, &target),
+                    deadline,
+                );
+            }
+        }
+        SnakePersonality::Timid => {
+            if *mode == SnakeMode::Hide {
+                return find_path(
+                    pathfinding_board,
+                    &self_pos,
+                    &find_hiding_spot(pathfinding_board, game_mode, forecast, view, deadline)?,
+                    deadline,
+                );
             } else {
-                utils::coord_to_pos(board, find_delicious_snake(board, head))
+                find_delicious_food(game_mode, forecast, view)
             }
         }
-        a => panic!("That personality isn't implemented yet: {:?}", a),
     };
-    return astar(
-        &self_pos,
-        |p| {
+    let goal_pos = match goal {
+        Some(goal) => utils::coord_to_pos(board, goal),
+        None => {
+            debug!("No goal for {:?} in {:?} mode", personality, mode);
+            return None;
+        }
+    };
+    find_path(pathfinding_board, &self_pos, &goal_pos, deadline)
+}
+
+// A* over (cell, turns taken) so bodies that will have moved out of the way by
+// the time we get there can be walked through. Turns stop counting once every
+// body has cleared, which keeps the search space finite. Gives up without a
+// path once the deadline has passed.
+pub fn find_path(
+    pathfinding_board: &PathfindingBoard,
+    from: &PathfindingPos,
+    goal_pos: &PathfindingPos,
+    deadline: &Deadline,
+) -> Option<(Vec<PathfindingPos>, u32)> {
+    let last_clear = pathfinding_board.last_clear();
+    let (path, cost) = astar(
+        &(*from, 0),
+        |&(p, elapsed)| {
+            // nothing left to explore ends the search
+            if deadline.expired() {
+                return Vec::new();
+            }
             pathfinding_board
-                .get_successors(p)
+                .get_successors_at(&p, elapsed)
                 .iter()
-                .map(|s| (s.pos, s.cost))
+                .map(|s| ((s.pos, (elapsed + 1).min(last_clear)), s.cost))
                 .collect::<Vec<_>>()
         },
-        |p| ((p.0 - goal_pos.0).abs() + (p.1 - goal_pos.1).abs()) as u32,
-        |p| *p == goal_pos,
-    );
+        |(p, _)| pathfinding_board.distance(p, goal_pos),
+        |(p, _)| p == goal_pos,
+    )?;
+    Some((path.into_iter().map(|(p, _)| p).collect(), cost))
 }
 
-fn find_delicious_snake<'a>(
-    board: &'a BattlesnakeBoard,
+// The neighbouring cell with the most space behind it. On a tie, the one with
+// the fewest open neighbours, so we hug walls and bodies and leave open space
+// for later.
+fn find_roomiest_step(
+    pathfinding_board: &PathfindingBoard,
+    board: &BattlesnakeBoard,
     head: &BattlesnakeCoord,
-) -> &'a BattlesnakeCoord {
-    let mut distances = Vec::new();
-    let us = &board.snakes[0];
-    println!("{:?}", us);
-    let other_snakes = &board.snakes[1..];
-    for s in other_snakes {
-        let d = utils::coord_distance(head, &s.head);
-        let mut smallest_snake_size = 999;
-        for s in other_snakes {
-            let osd = s.body.len();
-            if osd < smallest_snake_size {
-                smallest_snake_size = osd;
-            }
+) -> Option<(Vec<PathfindingPos>, u32)> {
+    let self_pos = utils::coord_to_pos(board, head);
+    let step = pathfinding_board
+        .get_successors(&self_pos)
+        .into_iter()
+        .map(|s| {
+            let area = space::reachable_area(pathfinding_board, &s.pos, &[self_pos]);
+            let open_neighbours = pathfinding_board.get_successors(&s.pos).len();
+            (s, area, open_neighbours)
+        })
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)))?;
+    debug!("Roomiest step: {:?}", step);
+    Some((vec![self_pos, step.0.pos], step.0.cost))
+}
+
+// Hungry goes after every piece of food regardless of who else is closer, so
+// food is ranked by how long the real path to it is. Ties go to the food the
+// nearest enemy is furthest from.
+fn find_closest_food_path(
+    pathfinding_board: &PathfindingBoard,
+    game_mode: GameMode,
+    forecast: Option<&Forecast>,
+    view: &BoardView,
+    deadline: &Deadline,
+) -> Option<(Vec<PathfindingPos>, u32)> {
+    let (board, head) = (view.board, &view.you.head);
+    let self_pos = utils::coord_to_pos(board, head);
+    let enemy_heads: Vec<&BattlesnakeCoord> = view.opponents.iter().map(|s| &s.head).collect();
+    let mut candidates = Vec::new();
+    for f in royale::safe_targets(forecast, head, &board.food) {
+        let path = find_path(
+            pathfinding_board,
+            &self_pos,
+            &utils::coord_to_pos(board, f),
+            deadline,
+        );
+        if let Some(path) = path {
+            let closest_enemy = enemy_heads
+                .iter()
+                .map(|h| utils::get_distance(game_mode, board, h, f))
+                .fold(f64::INFINITY, f64::min);
+            candidates.push((path.0.len(), closest_enemy, path));
         }
-        distances.push((d, smallest_snake_size));
     }
-    println!("{:?}", distances);
-    let min = distances
-        .iter()
-        .filter(|f| f.0 != 0.0)
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    println!("{:?}", min);
-    let min_clone = min.clone();
-    let idx = distances
+    debug!(
+        "Food candidates: {:?}",
+        candidates.iter().map(|c| (c.0, c.1)).collect::<Vec<_>>()
+    );
+    candidates
+        .into_iter()
+        .min_by(|a, b| a.0.cmp(&b.0).then(b.1.partial_cmp(&a.1).unwrap()))
+        .map(|c| c.2)
+}
+
+// The reachable cell that is furthest from every enemy head, preferring the
+// cheaper one to get to when two are equally far away. In royale games cells
+// the hazards could reach before we do are left out.
+fn find_hiding_spot(
+    pathfinding_board: &PathfindingBoard,
+    game_mode: GameMode,
+    forecast: Option<&Forecast>,
+    view: &BoardView,
+    deadline: &Deadline,
+) -> Option<PathfindingPos> {
+    let board = view.board;
+    let self_pos = utils::coord_to_pos(board, &view.you.head);
+    let reachable = dijkstra_all(&self_pos, |p| {
+        if deadline.expired() {
+            return Vec::new();
+        }
+        pathfinding_board
+            .get_successors(p)
+            .iter()
+            .map(|s| (s.pos, s.cost))
+            .collect::<Vec<_>>()
+    });
+    let enemy_heads: Vec<&BattlesnakeCoord> = view.opponents.iter().map(|s| &s.head).collect();
+    let spot = reachable
         .iter()
-        .position(|d| d == &min_clone)
-        .expect("cant find snake");
-    println!("snake idx {}", idx);
-    let snake = &other_snakes.get(idx).unwrap();
-    println!("{:?}", snake);
-    &snake.head
+        .filter(|(pos, (_, cost))| match forecast {
+            Some(forecast) => !forecast.is_hazardous_in(&utils::pos_to_coord(board, pos), *cost),
+            None => true,
+        })
+        .map(|(pos, (_, cost))| {
+            let c = utils::pos_to_coord(board, pos);
+            let closest_enemy = enemy_heads
+                .iter()
+                .map(|h| utils::get_distance(game_mode, board, &c, h))
+                .fold(f64::INFINITY, f64::min);
+            (*pos, closest_enemy, *cost)
+        })
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap().then(b.2.cmp(&a.2)));
+    debug!("Hiding spot: {:?}", spot);
+    spot.map(|s| s.0)
+}
+
+// The closest cell a shorter enemy's head could move into next turn, so we
+// meet it head on and win. Cells a snake our size or bigger could also reach
+// never count.
+fn find_delicious_snake(
+    game_mode: GameMode,
+    forecast: Option<&Forecast>,
+    view: &BoardView,
+) -> Option<BattlesnakeCoord> {
+    let (board, head) = (view.board, &view.you.head);
+    let risk = CollisionRisk::from_view(game_mode, view);
+    let target = royale::safe_targets(forecast, head, risk.opportunities())
+        .into_iter()
+        .map(|c| (utils::get_distance(game_mode, board, head, c), c))
+        .filter(|(d, _)| *d != 0.0)
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    debug!("Delicious snake: {:?}", target);
+    target.map(|(_, c)| c.clone())
 }
 
 fn find_delicious_food<'a>(
-    board: &'a BattlesnakeBoard,
-    head: &BattlesnakeCoord,
-) -> &'a BattlesnakeCoord {
-    if board.snakes.len() == 1 {
+    game_mode: GameMode,
+    forecast: Option<&Forecast>,
+    view: &BoardView<'a>,
+) -> Option<&'a BattlesnakeCoord> {
+    let (board, head) = (view.board, &view.you.head);
+    let food = royale::safe_targets(forecast, head, &board.food);
+    if view.opponents.is_empty() {
         let mut distances = Vec::new();
-        for f in &board.food {
-            let d = utils::coord_distance(head, f);
+        for f in &food {
+            let d = utils::get_distance(game_mode, board, head, f);
             distances.push(d);
         }
-        let min = distances
-            .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
-        let min_clone = min.clone();
-        let idx = distances
-            .iter()
-            .position(|d| d == &min_clone)
-            .expect("cant find food");
-        return board.food.get(idx).unwrap();
+        let min = distances.iter().min_by(|a, b| a.partial_cmp(b).unwrap())?;
+        let min_clone = *min;
+        let idx = distances.iter().position(|d| d == &min_clone)?;
+        food.get(idx).copied()
     } else {
         let mut distances = Vec::new();
-        let other_snakes = &board.snakes[1..];
-        for f in &board.food {
-            let d = utils::coord_distance(head, f);
+        for f in &food {
+            let d = utils::get_distance(game_mode, board, head, f);
             let mut closest_other_snake = 999.0;
-            for s in other_snakes {
-                let osd = utils::coord_distance(&s.head, f);
+            for s in &view.opponents {
+                let osd = utils::get_distance(game_mode, board, &s.head, f);
                 if osd < closest_other_snake {
                     closest_other_snake = osd;
                 }
             }
             distances.push((d, closest_other_snake));
         }
-        println!("{:?}", distances);
-        let min = distances
-            .iter()
-            .min_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap();
-        println!("{:?}", min);
-        let min_clone = min.clone();
-        let idx = distances
-            .iter()
-            .position(|d| d == &min_clone)
-            .expect("cant find food");
-        return board.food.get(idx).unwrap();
+        debug!("{:?}", distances);
+        let min = distances.iter().min_by(|a, b| a.partial_cmp(b).unwrap())?;
+        debug!("{:?}", min);
+        let min_clone = *min;
+        let idx = distances.iter().position(|d| d == &min_clone)?;
+        food.get(idx).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{find_path, find_roomiest_step};
+    use crate::logic::deadline::Deadline;
+    use crate::utils;
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+    use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
+
+    #[test]
+    fn roomiest_step_hugs_the_walls() {
+        let board = BattlesnakeBoard {
+            width: 5,
+            height: 5,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: Vec::new(),
+        };
+        // head at (1, 2). Left is a dead end with nothing around it, up, right
+        // and down all lead into the same space.
+        let pathfinding_board = PathfindingBoard::new(
+            vec![
+                "11111".to_string(),
+                "X1111".to_string(),
+                "1X111".to_string(),
+                "X1111".to_string(),
+                "1X111".to_string(),
+            ],
+            false,
+        );
+        let head = BattlesnakeCoord { x: 1, y: 2 };
+        let (path, cost) = find_roomiest_step(&pathfinding_board, &board, &head).unwrap();
+        // down only has one open neighbour, up has two and right three
+        let down = BattlesnakeCoord { x: 1, y: 1 };
+        assert_eq!(
+            path,
+            vec![
+                utils::coord_to_pos(&board, &head),
+                utils::coord_to_pos(&board, &down)
+            ]
+        );
+        assert_eq!(cost, 1);
+
+        // nowhere to go at all
+        let boxed_in = PathfindingBoard::new(
+            vec![
+                "11111".to_string(),
+                "1X111".to_string(),
+                "XXX11".to_string(),
+                "1X111".to_string(),
+                "11111".to_string(),
+            ],
+            false,
+        );
+        assert!(find_roomiest_step(&boxed_in, &board, &head).is_none());
+    }
+
+    #[test]
+    fn stops_searching_once_out_of_time() {
+        let pathfinding_board = PathfindingBoard::filled(11, 11, 1, false);
+        let (from, to) = (PathfindingPos(0, 0), PathfindingPos(10, 10));
+        assert!(find_path(&pathfinding_board, &from, &to, &Deadline::unbounded()).is_some());
+
+        let expired = Deadline::new(Instant::now(), Duration::ZERO, Duration::ZERO);
+        assert!(find_path(&pathfinding_board, &from, &to, &expired).is_none());
     }
 }
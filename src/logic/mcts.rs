@@ -0,0 +1,372 @@
+// Monte Carlo Tree Search with decoupled UCT for simultaneous moves. At every node
+// each snake picks its own move with UCB1 from its own statistics, and the
+// combination of those picks leads to the child node. New nodes are scored by
+// playing the game out with a rollout policy.
+
+use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::engine::{self, GameMode, Rules};
+use crate::{Battlesnake, Board as BattlesnakeBoard, Game};
+
+use super::danger::DangerWeights;
+use super::deadline::Deadline;
+use super::view::BoardView;
+use super::{minimax, pathfinding_move, SnakePersonality};
+
+const EXPLORATION: f64 = 1.41;
+const ROLLOUT_DEPTH: u32 = 40;
+
+// How long to keep searching: for a while, or for a fixed number of playouts so
+// the result doesn't depend on how fast the machine is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Budget {
+    Time(Duration),
+    Playouts(u32),
+}
+
+impl Budget {
+    fn spent(&self, started: Instant, playouts: u32) -> bool {
+        match self {
+            Budget::Time(budget) => started.elapsed() >= *budget,
+            Budget::Playouts(budget) => playouts >= *budget,
+        }
+    }
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    // "<ms>" or "<playouts>p"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let not_a_number = |_| format!("Not a number: {}", s);
+        match s.strip_suffix('p') {
+            Some(playouts) => Ok(Budget::Playouts(playouts.parse().map_err(not_a_number)?)),
+            None => Ok(Budget::Time(Duration::from_millis(
+                s.parse().map_err(not_a_number)?,
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Budget::Time(budget) => write!(f, "{}", budget.as_millis()),
+            Budget::Playouts(budget) => write!(f, "{}p", budget),
+        }
+    }
+}
+
+// Picks a move for `snake` during a playout
+pub trait RolloutPolicy {
+    fn choose(
+        &self,
+        game: &Game,
+        board: &BattlesnakeBoard,
+        snake: &Battlesnake,
+        rng: &mut StdRng,
+        deadline: &Deadline,
+    ) -> &'static str;
+}
+
+// The built in rollout policies: random sane moves, or playing out as one of
+// the personalities would
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rollout {
+    Random,
+    Personality(SnakePersonality),
+}
+
+impl FromStr for Rollout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("random") {
+            return Ok(Rollout::Random);
+        }
+        Ok(Rollout::Personality(SnakePersonality::from_str(s)?))
+    }
+}
+
+impl RolloutPolicy for Rollout {
+    fn choose(
+        &self,
+        game: &Game,
+        board: &BattlesnakeBoard,
+        snake: &Battlesnake,
+        rng: &mut StdRng,
+        deadline: &Deadline,
+    ) -> &'static str {
+        match self {
+            Rollout::Random => minimax::candidate_moves(GameMode::from_game(game), board, snake)
+                .choose(rng)
+                .copied()
+                .unwrap_or("up"),
+            Rollout::Personality(personality) => {
+                let view = BoardView::new(board, snake);
+                let weights = DangerWeights::for_personality(personality);
+                pathfinding_move(game, &view, personality, &weights, None, deadline)
+                    .0
+                    .chosen
+            }
+        }
+    }
+}
+
+struct SnakeStats {
+    id: String,
+    moves: Vec<&'static str>,
+    visits: Vec<u32>,
+    rewards: Vec<f64>,
+}
+
+impl SnakeStats {
+    fn select(&self, total: u32) -> usize {
+        let mut best = 0;
+        let mut best_score = f64::NEG_INFINITY;
+        for i in 0..self.moves.len() {
+            if self.visits[i] == 0 {
+                return i;
+            }
+            let n = self.visits[i] as f64;
+            let score = self.rewards[i] / n + EXPLORATION * ((total.max(1) as f64).ln() / n).sqrt();
+            if score > best_score {
+                best = i;
+                best_score = score;
+            }
+        }
+        best
+    }
+}
+
+struct Node {
+    board: BattlesnakeBoard,
+    visits: u32,
+    stats: Vec<SnakeStats>,
+    children: HashMap<Vec<&'static str>, Node>,
+}
+
+impl Node {
+    fn new(game_mode: GameMode, board: BattlesnakeBoard) -> Node {
+        let stats = board
+            .snakes
+            .iter()
+            .map(|s| {
+                let moves = minimax::candidate_moves(game_mode, &board, s);
+                SnakeStats {
+                    id: s.id.clone(),
+                    visits: vec![0; moves.len()],
+                    rewards: vec![0.0; moves.len()],
+                    moves,
+                }
+            })
+            .collect();
+        Node {
+            board,
+            visits: 0,
+            stats,
+            children: HashMap::new(),
+        }
+    }
+}
+
+// Runs playouts until `budget` is used up or the deadline passes, and returns
+// the move we tried most. All the randomness comes from `rng`, so a seeded
+// search with a playout budget always picks the same move.
+pub fn best_move(
+    game: &Game,
+    view: &BoardView,
+    budget: Budget,
+    policy: &dyn RolloutPolicy,
+    rng: &mut StdRng,
+    deadline: &Deadline,
+) -> Option<&'static str> {
+    let (board, you) = (view.board, view.you);
+    let started = Instant::now();
+    let rules = Rules::from_game(game);
+    let mut root = Node::new(rules.mode, board.clone());
+    let ours = root.stats.iter().position(|s| s.id == you.id)?;
+
+    let mut iterations = 0;
+    while iterations == 0 || !(budget.spent(started, iterations) || deadline.expired()) {
+        iterate(&mut root, game, &rules, policy, rng, deadline);
+        iterations += 1;
+    }
+
+    let stats = &root.stats[ours];
+    debug!(
+        "MCTS {} iterations: {:?}",
+        iterations,
+        stats
+            .moves
+            .iter()
+            .zip(stats.visits.iter().zip(stats.rewards.iter()))
+            .collect::<Vec<_>>()
+    );
+    let best = (0..stats.moves.len()).max_by_key(|&i| stats.visits[i])?;
+    Some(stats.moves[best])
+}
+
+// One selection/expansion/rollout/backpropagation pass. Returns the reward each
+// snake on this node's board got from the playout.
+fn iterate(
+    node: &mut Node,
+    game: &Game,
+    rules: &Rules,
+    policy: &dyn RolloutPolicy,
+    rng: &mut StdRng,
+    deadline: &Deadline,
+) -> HashMap<String, f64> {
+    node.visits += 1;
+    if node.board.snakes.len() <= 1 {
+        return score(&node.board, &node.stats);
+    }
+
+    let picks: Vec<usize> = node.stats.iter().map(|s| s.select(node.visits)).collect();
+    let joint: Vec<&'static str> = node
+        .stats
+        .iter()
+        .zip(picks.iter())
+        .map(|(s, &i)| s.moves[i])
+        .collect();
+
+    let rewards = match node.children.get_mut(&joint) {
+        Some(child) => iterate(child, game, rules, policy, rng, deadline),
+        None => {
+            let moves: HashMap<String, &str> = node
+                .stats
+                .iter()
+                .zip(joint.iter())
+                .map(|(s, m)| (s.id.clone(), *m))
+                .collect();
+            let next = engine::advance(rules, &node.board, &moves, rng).board;
+            let rewards = rollout(game, &next, &node.stats, rules, policy, rng, deadline);
+            let mut child = Node::new(rules.mode, next);
+            child.visits = 1;
+            node.children.insert(joint, child);
+            rewards
+        }
+    };
+
+    for (s, &i) in node.stats.iter_mut().zip(picks.iter()) {
+        s.visits[i] += 1;
+        s.rewards[i] += rewards.get(&s.id).copied().unwrap_or(0.0);
+    }
+    rewards
+}
+
+fn rollout(
+    game: &Game,
+    board: &BattlesnakeBoard,
+    stats: &[SnakeStats],
+    rules: &Rules,
+    policy: &dyn RolloutPolicy,
+    rng: &mut StdRng,
+    deadline: &Deadline,
+) -> HashMap<String, f64> {
+    let mut board = board.clone();
+    let mut turns = 0;
+    while board.snakes.len() > 1 && turns < ROLLOUT_DEPTH && !deadline.expired() {
+        let moves: HashMap<String, &str> = board
+            .snakes
+            .iter()
+            .map(|s| (s.id.clone(), policy.choose(game, &board, s, rng, deadline)))
+            .collect();
+        board = engine::advance(rules, &board, &moves, rng).board;
+        turns += 1;
+    }
+    score(&board, stats)
+}
+
+// Dead snakes get nothing. The survivors split the reward by length, so a
+// lone survivor takes all of it.
+fn score(board: &BattlesnakeBoard, stats: &[SnakeStats]) -> HashMap<String, f64> {
+    let total: usize = board.snakes.iter().map(|s| s.body.len()).sum();
+    stats
+        .iter()
+        .map(|s| {
+            let reward = match board.snakes.iter().find(|b| b.id == s.id) {
+                Some(alive) => alive.body.len() as f64 / total as f64,
+                None => 0.0,
+            };
+            (s.id.clone(), reward)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use super::{best_move, Budget, Rollout};
+    use crate::logic::deadline::Deadline;
+    use crate::logic::view::BoardView;
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Game};
+
+    #[test]
+    fn stays_out_of_the_corner_trap() {
+        let game = Game {
+            id: "game".to_string(),
+            ruleset: HashMap::new(),
+            timeout: 500,
+        };
+        // going up walks into a two cell pocket between the wall and our own body
+        let you = snake(
+            "you",
+            vec![
+                (0, 5),
+                (1, 5),
+                (2, 5),
+                (2, 6),
+                (2, 7),
+                (1, 7),
+                (0, 7),
+                (0, 8),
+                (0, 9),
+                (0, 10),
+                (1, 10),
+                (2, 10),
+                (3, 10),
+            ],
+        );
+        let enemy = snake("enemy", vec![(8, 2); 3]);
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), enemy],
+        };
+        let chosen = best_move(
+            &game,
+            &BoardView::new(&board, &you),
+            Budget::Playouts(1000),
+            &Rollout::Random,
+            &mut StdRng::seed_from_u64(7),
+            &Deadline::unbounded(),
+        );
+        assert_eq!(chosen, Some("down"));
+    }
+
+    #[test]
+    fn budgets_are_milliseconds_or_playouts() {
+        assert_eq!(
+            Budget::from_str("150"),
+            Ok(Budget::Time(Duration::from_millis(150)))
+        );
+        assert_eq!(Budget::from_str("500p"), Ok(Budget::Playouts(500)));
+        assert!(Budget::from_str("lots").is_err());
+        assert_eq!(Budget::Playouts(500).to_string(), "500p");
+    }
+}
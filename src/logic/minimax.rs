@@ -0,0 +1,278 @@
+// Depth-limited paranoid minimax over simultaneous moves. We pick a move, then
+// every opponent is assumed to pick whichever combination of moves is worst for
+// us. Boards are stepped as `BitBoard`s, which never spawn food, so the search
+// stays deterministic and copying a board is cheap.
+
+use itertools::Itertools;
+use log::debug;
+use std::cell::Cell;
+
+use crate::bitboard::{BitBoard, BitSet, MAX_CELLS, MAX_SNAKES};
+use crate::engine::{self, GameMode, Rules, MOVES};
+use crate::{Battlesnake, Board as BattlesnakeBoard, Game};
+
+use super::deadline::Deadline;
+use super::view::BoardView;
+
+pub const WIN: f64 = 1_000_000.0;
+pub const LOSS: f64 = -WIN;
+
+// Scores a board from the point of view of the snake at the given index.
+// Higher is better for us.
+pub type Evaluator = fn(&BitBoard, usize) -> f64;
+
+// Everything the search needs that doesn't change from node to node
+struct Search<'a> {
+    you: usize,
+    evaluate: Evaluator,
+    deadline: &'a Deadline,
+    timed_out: Cell<bool>,
+}
+
+// Searches one ply deeper at a time, up to `max_depth`, until the deadline
+// passes. Returns the best move and its value from the deepest search that
+// finished, or None if we aren't on the board or it's too big to search.
+pub fn best_move(
+    game: &Game,
+    view: &BoardView,
+    max_depth: u32,
+    evaluate: Evaluator,
+    deadline: &Deadline,
+) -> Option<(&'static str, f64)> {
+    let you = view.board.snakes.iter().position(|s| s.id == view.you.id)?;
+    let board = match BitBoard::from_board(&Rules::from_game(game), view.board) {
+        Some(board) => board,
+        None => {
+            debug!("Board too big for minimax");
+            return None;
+        }
+    };
+    let search = Search {
+        you,
+        evaluate,
+        deadline,
+        timed_out: Cell::new(false),
+    };
+    let mut best = None;
+    for depth in 1..=max_depth.max(1) {
+        match search_root(&search, &board, depth) {
+            Some(found) => {
+                debug!("Minimax depth {}: {:?}", depth, found);
+                best = Some(found);
+            }
+            None => break,
+        }
+        if deadline.expired() {
+            break;
+        }
+    }
+    best.map(|(m, value)| (MOVES[m as usize], value))
+}
+
+// A full search to `depth`, or None if it ran out of time. The first depth
+// always gets to finish so there's something to return.
+fn search_root(search: &Search, board: &BitBoard, depth: u32) -> Option<(u8, f64)> {
+    if !board.snakes[search.you].alive {
+        return None;
+    }
+    let mut best: Option<(u8, f64)> = None;
+    let mut alpha = f64::NEG_INFINITY;
+    for m in moves(board, search.you) {
+        let value = min_value(search, board, m, depth, alpha, f64::INFINITY);
+        if depth > 1 && search.timed_out.get() {
+            return None;
+        }
+        let better = match best {
+            Some((_, best_value)) => value > best_value,
+            None => true,
+        };
+        if better {
+            best = Some((m, value));
+        }
+        alpha = alpha.max(value);
+    }
+    best
+}
+
+fn max_value(search: &Search, board: &BitBoard, depth: u32, mut alpha: f64, beta: f64) -> f64 {
+    if !board.snakes[search.you].alive || depth == 0 || board.alive().count() == 1 {
+        return (search.evaluate)(board, search.you);
+    }
+    if search.deadline.expired() {
+        search.timed_out.set(true);
+        return (search.evaluate)(board, search.you);
+    }
+    let mut value = f64::NEG_INFINITY;
+    for m in moves(board, search.you) {
+        value = value.max(min_value(search, board, m, depth, alpha, beta));
+        if value >= beta {
+            return value;
+        }
+        alpha = alpha.max(value);
+    }
+    value
+}
+
+// The opponents' turn: all of them move together against our chosen move
+fn min_value(
+    search: &Search,
+    board: &BitBoard,
+    our_move: u8,
+    depth: u32,
+    alpha: f64,
+    mut beta: f64,
+) -> f64 {
+    let opponents: Vec<usize> = board.alive().filter(|i| *i != search.you).collect();
+    let options: Vec<Vec<u8>> = opponents.iter().map(|o| moves(board, *o)).collect();
+    let joint_moves: Vec<Vec<u8>> = if options.is_empty() {
+        vec![Vec::new()]
+    } else {
+        options.into_iter().multi_cartesian_product().collect()
+    };
+
+    let mut value = f64::INFINITY;
+    for joint in joint_moves {
+        let mut moves = [0; MAX_SNAKES];
+        moves[search.you] = our_move;
+        for (o, m) in opponents.iter().zip(joint) {
+            moves[*o] = m;
+        }
+        let mut next = *board;
+        next.advance(&moves);
+        value = value.min(max_value(search, &next, depth - 1, alpha, beta));
+        if value <= alpha {
+            return value;
+        }
+        beta = beta.min(value);
+    }
+    value
+}
+
+// `BitBoard::moves`, with "up" for a snake that has nowhere to go so it still
+// picks something and dies
+fn moves(board: &BitBoard, snake: usize) -> Vec<u8> {
+    let moves: Vec<u8> = board.moves(snake).collect();
+    if moves.is_empty() {
+        vec![0]
+    } else {
+        moves
+    }
+}
+
+// Moves that don't leave the board or turn back into the neck. A snake with no
+// such move still has to pick something, so it gets "up" and dies.
+pub fn candidate_moves(
+    game_mode: GameMode,
+    board: &BattlesnakeBoard,
+    snake: &Battlesnake,
+) -> Vec<&'static str> {
+    let neck = snake.body.get(1).filter(|n| **n != snake.head);
+    let moves: Vec<&'static str> = MOVES
+        .iter()
+        .filter(|m| match engine::step(game_mode, board, &snake.head, m) {
+            Some(next) => Some(&next) != neck,
+            None => false,
+        })
+        .copied()
+        .collect();
+    if moves.is_empty() {
+        vec!["up"]
+    } else {
+        moves
+    }
+}
+
+// Room to move matters most, then being longer than the competition, then health
+pub fn default_evaluation(board: &BitBoard, you: usize) -> f64 {
+    let me = board.snakes[you];
+    if !me.alive {
+        return LOSS;
+    }
+    let opponents: Vec<usize> = board.alive().filter(|i| *i != you).collect();
+    if opponents.is_empty() {
+        return WIN;
+    }
+    let longest = opponents
+        .iter()
+        .map(|o| board.snakes[*o].length)
+        .max()
+        .unwrap_or(0);
+    let area = open_area(board, me.head);
+    area as f64 + 5.0 * (me.length as f64 - longest as f64) + 0.1 * me.health as f64
+        - 10.0 * opponents.len() as f64
+}
+
+// Cells reachable from `start` without crossing a body or a head. Tails are
+// left open since they move out of the way next turn, except in constrictor or
+// when the snake has just eaten.
+fn open_area(board: &BitBoard, start: u16) -> usize {
+    let mut blocked = board.bodies;
+    for i in board.alive() {
+        let s = board.snakes[i];
+        blocked.set(s.head);
+        if !board.constrictor && s.stacked == 0 && s.tail != s.head {
+            blocked.clear(s.tail);
+        }
+    }
+    let mut seen = BitSet::default();
+    let mut queue = [0u16; MAX_CELLS];
+    let (mut read, mut write) = (0, 1);
+    queue[0] = start;
+    while read < write {
+        let cell = queue[read];
+        read += 1;
+        for direction in 0..MOVES.len() as u8 {
+            if let Some(next) = board.neighbour(cell, direction) {
+                if !blocked.contains(next) && !seen.contains(next) {
+                    seen.set(next);
+                    queue[write] = next;
+                    write += 1;
+                }
+            }
+        }
+    }
+    seen.count() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use std::time::{Duration, Instant};
+
+    use super::{best_move, default_evaluation};
+    use crate::logic::deadline::Deadline;
+    use crate::logic::view::BoardView;
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Game};
+
+    #[test]
+    fn avoids_head_to_head_with_bigger_snake() {
+        let game = Game {
+            id: "game".to_string(),
+            ruleset: HashMap::new(),
+            timeout: 500,
+        };
+        // the only way we can die next turn is by moving right into the cell
+        // the bigger snake can also reach
+        let you = snake("you", vec![(3, 5), (3, 4), (3, 3)]);
+        let big = snake("big", vec![(5, 5), (6, 5), (7, 5), (8, 5)]);
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), big],
+        };
+        let deadline = Deadline::new(Instant::now(), Duration::from_secs(60), Duration::ZERO);
+        let (chosen, _) = best_move(
+            &game,
+            &BoardView::new(&board, &you),
+            1,
+            default_evaluation,
+            &deadline,
+        )
+        .unwrap();
+        assert_ne!(chosen, "right");
+    }
+}
@@ -10,20 +10,41 @@
 // To get you started we've included code to prevent your Battlesnake from moving backwards.
 // For more info see docs.battlesnake.com
 
+pub mod collision;
+pub mod danger;
+pub mod deadline;
+mod fallback;
 mod goal;
+pub mod mcts;
+pub mod minimax;
+pub mod royale;
+pub mod session;
+mod space;
+pub mod view;
 
-use core::panic;
-use log::info;
+use log::{debug, info};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde_json::{json, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::panic;
+use std::str::FromStr;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
+use crate::engine::GameMode;
 use crate::learning::MyState;
 use crate::utils::{self};
 use crate::AGENT_TRAINER;
 use crate::{Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
-use rust_pathfinding::PathfindingPos;
+use deadline::Deadline;
+use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
+use view::BoardView;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SnakePersonality {
     Hungry,     // Eats food no matter what
     Timid,      // Avoid snakes at all costs
@@ -31,10 +52,156 @@ pub enum SnakePersonality {
     Snacky,     // Eats food when it's safe to do so
 }
 
-#[derive(Debug, PartialEq)]
+impl FromStr for SnakePersonality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hungry" => Ok(SnakePersonality::Hungry),
+            "timid" => Ok(SnakePersonality::Timid),
+            "headhunter" => Ok(SnakePersonality::HeadHunter),
+            "snacky" => Ok(SnakePersonality::Snacky),
+            _ => Err(format!("Unknown personality: {}", s)),
+        }
+    }
+}
+
+// Where the move comes from: a path to the personality's goal, or a search
+// that looks ahead at what the other snakes could do
+#[derive(Debug, Clone, Copy)]
+pub enum DecisionSource {
+    Pathfinding,
+    Minimax {
+        depth: u32,
+        evaluate: minimax::Evaluator,
+    },
+    Mcts {
+        budget: mcts::Budget,
+        rollout: mcts::Rollout,
+    },
+}
+
+impl FromStr for DecisionSource {
+    type Err = String;
+
+    // "astar", "minimax[=<depth>]" or "mcts[=<budget>[,<rollout>]]", where the
+    // budget is milliseconds or a number of playouts like "500p"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once('=') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (s, None),
+        };
+        let mut args = arg
+            .map(|a| a.split(',').collect::<Vec<_>>())
+            .unwrap_or_default();
+        let rollout = if args.len() > 1 { args.pop() } else { None };
+        let number = |default: u64| match args.first() {
+            Some(arg) => arg
+                .parse::<u64>()
+                .map_err(|_| format!("Not a number: {}", arg)),
+            None => Ok(default),
+        };
+        match name.to_lowercase().as_str() {
+            "astar" | "pathfinding" => Ok(DecisionSource::Pathfinding),
+            "minimax" => Ok(DecisionSource::Minimax {
+                depth: number(2)? as u32,
+                evaluate: minimax::default_evaluation,
+            }),
+            "mcts" => Ok(DecisionSource::Mcts {
+                budget: mcts::Budget::from_str(args.first().copied().unwrap_or("200"))?,
+                rollout: match rollout {
+                    Some(r) => mcts::Rollout::from_str(r)?,
+                    None => mcts::Rollout::Random,
+                },
+            }),
+            _ => Err(format!("Unknown decision source: {}", s)),
+        }
+    }
+}
+
+// Everything that decides how a snake plays, so different strategies can be
+// pitted against each other without redeploying
+#[derive(Debug, Clone)]
+pub struct StrategyConfig {
+    pub personality: SnakePersonality,
+    pub decision: DecisionSource,
+    // how much pathfinding shies away from each kind of risky cell
+    pub danger: danger::DangerWeights,
+    // makes every random choice repeatable, see `decision_rng`
+    pub seed: Option<u64>,
+}
+
+impl StrategyConfig {
+    // Pathfinding as `personality`, with its own danger weights
+    pub fn for_personality(personality: SnakePersonality) -> StrategyConfig {
+        StrategyConfig {
+            personality,
+            decision: DecisionSource::Pathfinding,
+            danger: danger::DangerWeights::for_personality(&personality),
+            seed: None,
+        }
+    }
+}
+
+impl Default for StrategyConfig {
+    fn default() -> StrategyConfig {
+        StrategyConfig::for_personality(SnakePersonality::HeadHunter)
+    }
+}
+
+impl FromStr for StrategyConfig {
+    type Err = String;
+
+    // PERSONALITY[:DECISION], e.g. "snacky" or "headhunter:minimax=3"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (personality, decision) = match s.split_once(':') {
+            Some((personality, decision)) => (personality, DecisionSource::from_str(decision)?),
+            None => (s, DecisionSource::Pathfinding),
+        };
+        Ok(StrategyConfig {
+            decision,
+            ..StrategyConfig::for_personality(SnakePersonality::from_str(personality)?)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SnakeMode {
     Eat,
     Kill,
+    Hide,
+}
+
+// The move we settled on and what we had in mind when we picked it
+#[derive(Debug, Clone)]
+pub struct Decision {
+    pub chosen: &'static str,
+    // only when pathfinding followed the personality's path, searches and
+    // fallback moves have no mode
+    pub mode: Option<SnakeMode>,
+    // the personality's path when the move is its first step, starting at our
+    // head
+    pub path: Option<Vec<BattlesnakeCoord>>,
+}
+
+impl Decision {
+    fn without_plan(chosen: &'static str) -> Decision {
+        Decision {
+            chosen,
+            mode: None,
+            path: None,
+        }
+    }
+
+    // Where the path we followed was heading
+    pub fn goal(&self) -> Option<&BattlesnakeCoord> {
+        self.path.as_ref().and_then(|path| path.last())
+    }
+
+    // The body of the /move response
+    pub fn response(&self) -> Value {
+        json!({ "move": self.chosen })
+    }
 }
 
 // Logic Loop
@@ -52,18 +219,19 @@ pub enum SnakeMode {
 // TIP: If you open your Battlesnake URL in a browser you should see this data
 pub fn info() -> Value {
     info!("INFO");
-    return json!({
+    json!({
         "apiversion": "1",
         "author": "", // TODO: Your Battlesnake Username
         "color": "#888888", // TODO: Choose color
         "head": "default", // TODO: Choose head
         "tail": "default", // TODO: Choose tail
-    });
+    })
 }
 
 // start is called when your Battlesnake begins a game
-pub fn start(_game: &Game, _turn: &u32, _board: &BattlesnakeBoard, _you: &Battlesnake) {
+pub fn start(game: &Game, _turn: &u32, _board: &BattlesnakeBoard, _you: &Battlesnake) {
     info!("GAME START");
+    session::begin(game);
     let trainer = Arc::clone(&AGENT_TRAINER);
     let trainer_lock = trainer.lock().unwrap();
     let action = trainer_lock.best_action(&MyState {
@@ -75,52 +243,505 @@ pub fn start(_game: &Game, _turn: &u32, _board: &BattlesnakeBoard, _you: &Battle
 }
 
 // end is called when your Battlesnake finishes a game
-pub fn end(_game: &Game, _turn: &u32, _board: &BattlesnakeBoard, _you: &Battlesnake) {
+pub fn end(game: &Game, _turn: &u32, _board: &BattlesnakeBoard, _you: &Battlesnake) {
     info!("GAME OVER");
+    session::finish(game);
 }
 
+// The name of the thread every move is decided on, so panics there can be told
+// apart from the rest
+pub const DECISION_THREAD: &str = "decision";
+
+// How much sooner than our answer is due the decision has to stop thinking,
+// so a search that uses all of its time still gets its move back to us
+pub const DECISION_RESERVE: Duration = Duration::from_millis(20);
+
 // move is called on every turn and returns your next move
 // Valid moves are "up", "down", "left", or "right"
 // See https://docs.battlesnake.com/api/example-move for available data
-pub fn get_move(_game: &Game, turn: &u32, board: &BattlesnakeBoard, you: &Battlesnake) -> Value {
-    let my_head = &you.body[0]; // Coordinates of your head
-    let personality = SnakePersonality::HeadHunter;
+pub fn get_move(
+    game: &Game,
+    turn: &u32,
+    view: &BoardView,
+    config: &StrategyConfig,
+    deadline: &Deadline,
+) -> Decision {
+    // something to answer with if the real decision isn't back in time
+    let safe = fallback::safe_move(GameMode::from_game(game), view);
+    // the decision thread only updates sessions, so one that runs late can't
+    // start the game over after /end
+    session::join(game);
+
+    let (sender, receiver) = mpsc::channel();
+    let decision = {
+        let (game, turn, config) = (game.clone(), *turn, config.clone());
+        let deadline = deadline.reserve(DECISION_RESERVE);
+        let (board, you) = (view.board.clone(), view.you.clone());
+        thread::Builder::new()
+            .name(DECISION_THREAD.to_string())
+            .spawn(move || {
+                let view = BoardView::new(&board, &you);
+                let _ = sender.send(decide(&game, &turn, &view, &config, &deadline));
+            })
+            .expect("spawn decision thread")
+    };
+    let decided = match receiver.recv_timeout(deadline.remaining()) {
+        Ok(decided) => decided,
+        Err(RecvTimeoutError::Timeout) => {
+            info!("MOVE {}: {} (out of time, safe move)", turn, safe);
+            Decision::without_plan(safe)
+        }
+        // the decision died without answering, so pass its panic on
+        Err(RecvTimeoutError::Disconnected) => match decision.join() {
+            Err(cause) => panic::resume_unwind(cause),
+            Ok(()) => Decision::without_plan(safe),
+        },
+    };
+
+    // remember this turn for the next one
+    session::record_turn(
+        game,
+        &view.you.id,
+        session::LastTurn {
+            turn: *turn,
+            board: view.board.clone(),
+            chosen: decided.chosen,
+            goal: decided.goal().cloned(),
+        },
+    );
+    decided
+}
 
+fn decide(
+    game: &Game,
+    turn: &u32,
+    view: &BoardView,
+    config: &StrategyConfig,
+    deadline: &Deadline,
+) -> Decision {
+    // 0. keep track of the royale hazards every turn, whichever way we decide,
+    // so the shrink history has no gaps when we fall back on pathfinding
+    let forecast = royale::observe(game, turn, view.board);
+
+    // 1. search for the best move outright, if that's how we're playing
+    match config.decision {
+        DecisionSource::Pathfinding => {}
+        DecisionSource::Minimax { depth, evaluate } => {
+            match minimax::best_move(game, view, depth, evaluate, deadline) {
+                Some((chosen, value)) if value > minimax::LOSS => {
+                    info!(
+                        "MOVE {}: {} (minimax up to depth {}, value {})",
+                        turn, chosen, depth, value
+                    );
+                    return searched(game, view, config, forecast.as_ref(), deadline, chosen);
+                }
+                _ => info!("Minimax sees no way out, falling back on pathfinding"),
+            }
+        }
+        DecisionSource::Mcts { budget, rollout } => {
+            let mut rng = decision_rng(config, game, turn, view.you);
+            if let Some(chosen) = mcts::best_move(game, view, budget, &rollout, &mut rng, deadline)
+            {
+                info!(
+                    "MOVE {}: {} (mcts {}, {:?} rollouts)",
+                    turn, chosen, budget, rollout
+                );
+                return searched(game, view, config, forecast.as_ref(), deadline, chosen);
+            }
+            info!("MCTS found nothing, falling back on pathfinding");
+        }
+    }
+
+    // 2. otherwise go where the personality wants to go, steering clear of
+    // where the royale hazards are heading
+    let (decided, reason) = pathfinding_move(
+        game,
+        view,
+        &config.personality,
+        &config.danger,
+        forecast.as_ref(),
+        deadline,
+    );
+
+    // 3. MOVE THERE!
+    info!("MOVE {}: {} ({})", turn, decided.chosen, reason);
+    decided
+}
+
+// A move a search picked. Searches have no mode, but when the move is the first
+// step of the personality's path we're still following that path.
+fn searched(
+    game: &Game,
+    view: &BoardView,
+    config: &StrategyConfig,
+    forecast: Option<&royale::Forecast>,
+    deadline: &Deadline,
+    chosen: &'static str,
+) -> Decision {
+    // the search used up the time, the move matters more than the path
+    if deadline.expired() {
+        return Decision::without_plan(chosen);
+    }
+    let (_, _, result) = plan(
+        game,
+        view,
+        &config.personality,
+        &config.danger,
+        forecast,
+        deadline,
+    );
+    let path = result
+        .filter(|moves| determine_next_move(moves, view.board, &view.you.body[0]) == Some(chosen))
+        .map(|moves| path_coords(view.board, &moves.0));
+    Decision {
+        chosen,
+        mode: None,
+        path,
+    }
+}
+
+// Where a decision gets its randomness from. With a seed, the same snake gets
+// the same numbers every time it sees the same turn of the same game.
+fn decision_rng(config: &StrategyConfig, game: &Game, turn: &u32, you: &Battlesnake) -> StdRng {
+    match config.seed {
+        Some(seed) => {
+            let mut hasher = DefaultHasher::new();
+            (seed, &game.id, turn, &you.id).hash(&mut hasher);
+            StdRng::seed_from_u64(hasher.finish())
+        }
+        None => StdRng::from_entropy(),
+    }
+}
+
+// The cells pathfinding would head through to reach the strategy's goal,
+// starting at our head. None when the goal is out of reach. Takes as long as
+// it needs, it's for looking at games afterwards.
+pub fn planned_path(
+    game: &Game,
+    turn: &u32,
+    view: &BoardView,
+    config: &StrategyConfig,
+) -> Option<Vec<BattlesnakeCoord>> {
+    let forecast = royale::observe(game, turn, view.board);
+    let (_, _, result) = plan(
+        game,
+        view,
+        &config.personality,
+        &config.danger,
+        forecast.as_ref(),
+        &Deadline::unbounded(),
+    );
+    Some(path_coords(view.board, &result?.0))
+}
+
+fn path_coords(board: &BattlesnakeBoard, path: &[PathfindingPos]) -> Vec<BattlesnakeCoord> {
+    path.iter().map(|p| utils::pos_to_coord(board, p)).collect()
+}
+
+// The mode we're in, the board pathfinding works on and the path to the
+// personality's goal over it
+fn plan(
+    game: &Game,
+    view: &BoardView,
+    personality: &SnakePersonality,
+    weights: &danger::DangerWeights,
+    forecast: Option<&royale::Forecast>,
+    deadline: &Deadline,
+) -> (
+    SnakeMode,
+    PathfindingBoard,
+    Option<(Vec<PathfindingPos>, u32)>,
+) {
     // WHAT MODE AM I IN?????
-    let mode = utils::get_snake_mode(board, you, &personality);
-    println!("Snake Mode: {:?}", mode);
+    let mode = utils::get_snake_mode(view, personality);
+    debug!("Snake Mode: {:?}", mode);
 
-    // main logic
+    // avoid directly hitting snakes, and hazards that would hurt too much
+    let mut pathfinding_board =
+        utils::build_pathfinding_board_with_hazards(personality, game, view);
 
-    // 2. avoid directly hitting snakes
-    let (pathfinding_board, _) =
-        utils::build_pathfinding_board_with_hazards(&personality, board, &you);
+    // make risky cells cost more so paths favour open, safe terrain
+    let game_mode = GameMode::from_game(game);
+    danger::apply(&mut pathfinding_board, game_mode, view, weights);
 
-    // 3. determine goal
-    let result = goal::determine_goal(&personality, &mode, &pathfinding_board, board, my_head);
-    let moves = if let Some(moves) = result {
-        moves
-    } else {
-        panic!("No moves to make!");
-    };
+    // determine goal
+    let result = goal::determine_goal(
+        personality,
+        &mode,
+        &pathfinding_board,
+        game_mode,
+        forecast,
+        view,
+        deadline,
+    );
+    (mode, pathfinding_board, result)
+}
 
-    // 4. ?
+// The move along the path to the personality's goal, or the best fallback when
+// that isn't safe, along with the reason it was picked
+fn pathfinding_move(
+    game: &Game,
+    view: &BoardView,
+    personality: &SnakePersonality,
+    weights: &danger::DangerWeights,
+    forecast: Option<&royale::Forecast>,
+    deadline: &Deadline,
+) -> (Decision, &'static str) {
+    let (board, you) = (view.board, view.you);
+    let my_head = &you.body[0]; // Coordinates of your head
+    let game_mode = GameMode::from_game(game);
+    let (mode, pathfinding_board, result) =
+        plan(game, view, personality, weights, forecast, deadline);
 
-    // 5. MOVE THERE!
-    let chosen = determine_next_move(&moves, board, my_head);
-    info!("MOVE {}: {}", turn, chosen);
-    return json!({ "move": chosen });
+    // don't follow the path into a pocket we can't fit in, and fall back on
+    // something safe-ish when the goal is out of reach
+    let spaces = space::reachable_space(&pathfinding_board, board, my_head);
+    let goal_move = result
+        .as_ref()
+        .and_then(|moves| determine_next_move(moves, board, my_head));
+    let goal_move = goal_move.filter(|m| {
+        let fits = space::fits(&spaces, m, you.body.len());
+        if !fits {
+            debug!("Refusing {}, not enough room for our body", m);
+        }
+        fits
+    });
+    if let Some(chosen) = goal_move {
+        let decided = Decision {
+            chosen,
+            mode: Some(mode),
+            path: result.as_ref().map(|moves| path_coords(board, &moves.0)),
+        };
+        (decided, "path to goal")
+    } else if let Some(chosen) = fallback::chase_tail(&pathfinding_board, view, deadline) {
+        (
+            Decision::without_plan(chosen),
+            "no safe path to goal, chasing tail",
+        )
+    } else if let Some(chosen) = space::largest(&spaces) {
+        (
+            Decision::without_plan(chosen),
+            "no path to goal or tail, moving into largest area",
+        )
+    } else if let Some(chosen) = fallback::any_in_bounds_move(game_mode, board, my_head) {
+        (
+            Decision::without_plan(chosen),
+            "no open cells, staying on the board",
+        )
+    } else {
+        (Decision::without_plan("up"), "no moves at all")
+    }
 }
 
 fn determine_next_move(
     moves: &(Vec<PathfindingPos>, u32),
     board: &BattlesnakeBoard,
     head: &BattlesnakeCoord,
-) -> &'static str {
-    println!("Current Pathfinder Path: {:?}", moves);
-    let next_move = moves.0.get(1).expect("No more moves to make");
+) -> Option<&'static str> {
+    debug!("Current Pathfinder Path: {:?}", moves);
+    let next_move = moves.0.get(1)?;
     let converted_next_move = utils::pos_to_coord(board, next_move);
-    println!("Next Pathfinder Move: {:?}", next_move);
-    println!("Next Battlesnake Move: {:?}", converted_next_move);
-    utils::get_next_move_from_coord(head, &converted_next_move)
+    debug!("Next Pathfinder Move: {:?}", next_move);
+    debug!("Next Battlesnake Move: {:?}", converted_next_move);
+    Some(utils::get_next_move_from_coord(head, &converted_next_move))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::time::{Duration, Instant};
+
+    use super::{
+        danger, get_move, mcts, royale, session, BoardView, Deadline, DecisionSource,
+        SnakePersonality, StrategyConfig,
+    };
+    use crate::test_support::snake;
+    use crate::{Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
+
+    #[test]
+    fn answers_without_food_or_enemies() {
+        let game = Game {
+            id: "game".to_string(),
+            ruleset: HashMap::new(),
+            timeout: 500,
+        };
+        let you = Battlesnake {
+            health: 50,
+            ..snake("you", vec![(0, 0), (1, 0), (2, 0)])
+        };
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone()],
+        };
+        for personality in [
+            SnakePersonality::Hungry,
+            SnakePersonality::Timid,
+            SnakePersonality::HeadHunter,
+            SnakePersonality::Snacky,
+        ] {
+            let config = StrategyConfig::for_personality(personality);
+            let decided = get_move(
+                &game,
+                &0,
+                &BoardView::new(&board, &you),
+                &config,
+                &Deadline::start(&game),
+            );
+            assert_eq!(decided.chosen, "up");
+        }
+    }
+
+    #[test]
+    fn parses_strategies() {
+        let config = StrategyConfig::from_str("snacky").unwrap();
+        assert_eq!(config.personality, SnakePersonality::Snacky);
+        assert!(matches!(config.decision, DecisionSource::Pathfinding));
+        assert_eq!(
+            config.danger,
+            danger::DangerWeights::for_personality(&SnakePersonality::Snacky)
+        );
+        assert_eq!(config.seed, None);
+
+        let config = StrategyConfig::from_str("HeadHunter:minimax=3").unwrap();
+        assert_eq!(config.personality, SnakePersonality::HeadHunter);
+        assert!(matches!(
+            config.decision,
+            DecisionSource::Minimax { depth: 3, .. }
+        ));
+
+        let config = StrategyConfig::from_str("timid:mcts=500p,hungry").unwrap();
+        assert!(matches!(
+            config.decision,
+            DecisionSource::Mcts {
+                budget: mcts::Budget::Playouts(500),
+                rollout: mcts::Rollout::Personality(SnakePersonality::Hungry),
+            }
+        ));
+
+        assert!(StrategyConfig::from_str("sleepy").is_err());
+        assert!(StrategyConfig::from_str("timid:minimax=deep").is_err());
+        assert!(StrategyConfig::from_str("timid:dijkstra").is_err());
+    }
+
+    #[test]
+    fn searches_still_keep_the_royale_history() {
+        let game = Game {
+            id: "royale-search".to_string(),
+            ruleset: HashMap::from([("name".to_string(), json!("royale"))]),
+            timeout: 500,
+        };
+        let snake = |id: &str, x: u32| snake(id, vec![(x, 5), (x, 4), (x, 3)]);
+        let you = snake("you", 4);
+        let board = |columns: u32| BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: (0..columns)
+                .flat_map(|x| (0..11).map(move |y| BattlesnakeCoord { x, y }))
+                .collect(),
+            snakes: vec![you.clone(), snake("them", 8)],
+        };
+        let config = StrategyConfig::from_str("headhunter:minimax=1").unwrap();
+        for (turn, columns) in [(9, 0), (10, 1), (19, 1), (20, 2)] {
+            let board = board(columns);
+            let decided = get_move(
+                &game,
+                &turn,
+                &BoardView::new(&board, &you),
+                &config,
+                &Deadline::start(&game),
+            );
+            // searched moves don't pretend to have a mode
+            assert_eq!(decided.mode, None);
+        }
+        // both shrinks were seen even though minimax made every move
+        let forecast = royale::observe(&game, &21, &board(2)).unwrap();
+        assert_eq!(forecast.shrink_every, 10);
+        assert_eq!(forecast.next_shrink, 30);
+        session::finish(&game);
+    }
+
+    #[test]
+    fn searches_remember_the_goal_they_head_for() {
+        let game = Game {
+            id: "search-goal".to_string(),
+            ruleset: HashMap::new(),
+            timeout: 500,
+        };
+        let you = snake("you", vec![(4, 5), (4, 4), (4, 3)]);
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: vec![BattlesnakeCoord { x: 4, y: 7 }],
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), snake("them", vec![(9, 1), (9, 0), (10, 0)])],
+        };
+        let config = StrategyConfig::from_str("snacky:minimax=1").unwrap();
+        session::begin(&game);
+        let decided = get_move(
+            &game,
+            &0,
+            &BoardView::new(&board, &you),
+            &config,
+            &Deadline::unbounded(),
+        );
+        assert_eq!(decided.chosen, "up");
+        assert_eq!(decided.mode, None);
+        let food = BattlesnakeCoord { x: 4, y: 7 };
+        assert_eq!(decided.goal(), Some(&food));
+        let remembered = session::update(&game, |s| s.last_turns["you"].goal.clone());
+        assert_eq!(remembered, Some(Some(food)));
+        session::finish(&game);
+    }
+
+    #[test]
+    fn deep_searches_answer_before_the_deadline() {
+        let game = Game {
+            id: "deep-search".to_string(),
+            ruleset: HashMap::new(),
+            timeout: 500,
+        };
+        // going up, the safe move, walks into a two cell pocket between the
+        // wall and our own body
+        let you = snake(
+            "you",
+            vec![
+                (0, 5),
+                (1, 5),
+                (2, 5),
+                (2, 6),
+                (2, 7),
+                (1, 7),
+                (0, 7),
+                (0, 8),
+                (0, 9),
+                (0, 10),
+                (1, 10),
+                (2, 10),
+                (3, 10),
+            ],
+        );
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), snake("them", vec![(8, 2), (8, 1), (8, 0)])],
+        };
+        let config = StrategyConfig::from_str("headhunter:minimax=1000").unwrap();
+        let deadline = Deadline::new(
+            Instant::now(),
+            Duration::from_millis(200),
+            Duration::from_millis(0),
+        );
+        let decided = get_move(&game, &0, &BoardView::new(&board, &you), &config, &deadline);
+        // minimax runs until the deadline but its answer still gets back in time
+        assert_eq!(decided.chosen, "down");
+        session::finish(&game);
+    }
 }
@@ -0,0 +1,232 @@
+// Royale games shrink the safe area by adding a row or column of hazards on a
+// random side every few turns. We can't know which side is next, but we can
+// work out when the next shrinks are due and treat every cell that could be
+// swallowed by then as unsafe.
+
+use log::debug;
+use serde_json::Value;
+
+use super::session;
+use crate::engine::GameMode;
+use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
+
+// What the official engine uses when `shrinkEveryNTurns` isn't sent
+const DEFAULT_SHRINK_EVERY: u32 = 25;
+
+// The rectangle of cells that aren't hazards yet, inclusive on every side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SafeArea {
+    pub min_x: u32,
+    pub max_x: u32,
+    pub min_y: u32,
+    pub max_y: u32,
+}
+
+impl SafeArea {
+    pub fn from_board(board: &BattlesnakeBoard) -> Option<SafeArea> {
+        let mut area: Option<SafeArea> = None;
+        for x in 0..board.width {
+            for y in 0..board.height {
+                if board.hazards.contains(&BattlesnakeCoord { x, y }) {
+                    continue;
+                }
+                area = Some(match area {
+                    None => SafeArea {
+                        min_x: x,
+                        max_x: x,
+                        min_y: y,
+                        max_y: y,
+                    },
+                    Some(a) => SafeArea {
+                        min_x: a.min_x.min(x),
+                        max_x: a.max_x.max(x),
+                        min_y: a.min_y.min(y),
+                        max_y: a.max_y.max(y),
+                    },
+                });
+            }
+        }
+        area
+    }
+
+    // How many shrinks it takes before `c` is a hazard, assuming they all come
+    // from the nearest side. 0 means it already is one.
+    pub fn shrinks_until_hazard(&self, c: &BattlesnakeCoord) -> u32 {
+        if c.x < self.min_x || c.x > self.max_x || c.y < self.min_y || c.y > self.max_y {
+            return 0;
+        }
+        1 + (c.x - self.min_x)
+            .min(self.max_x - c.x)
+            .min(c.y - self.min_y)
+            .min(self.max_y - c.y)
+    }
+}
+
+// Kept in the game's session so shrinks can be told apart from turn to turn
+#[derive(Debug, Default)]
+pub struct RoyaleHistory {
+    last_area: Option<SafeArea>,
+    shrink_turns: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Forecast {
+    pub turn: u32,
+    pub area: SafeArea,
+    pub shrink_every: u32,
+    pub next_shrink: u32,
+}
+
+impl Forecast {
+    // Number of shrinks that will have happened `turns_ahead` turns from now
+    pub fn shrinks_within(&self, turns_ahead: u32) -> u32 {
+        let horizon = self.turn + turns_ahead;
+        if horizon < self.next_shrink {
+            return 0;
+        }
+        1 + (horizon - self.next_shrink) / self.shrink_every
+    }
+
+    // Whether `c` could be a hazard `turns_ahead` turns from now
+    pub fn is_hazardous_in(&self, c: &BattlesnakeCoord, turns_ahead: u32) -> bool {
+        self.area.shrinks_until_hazard(c) <= self.shrinks_within(turns_ahead)
+    }
+}
+
+// Records this turn's hazards in the game's session, if it has one, and
+// forecasts the shrinks to come. Returns None outside of royale games.
+pub fn observe(game: &Game, turn: &u32, board: &BattlesnakeBoard) -> Option<Forecast> {
+    if GameMode::from_game(game) != GameMode::Royale {
+        return None;
+    }
+    let area = SafeArea::from_board(board)?;
+    let history = session::update(game, |session| {
+        let record = &mut session.royale;
+        if record.last_area.is_some() && record.last_area != Some(area) {
+            record.shrink_turns.push(*turn);
+        }
+        record.last_area = Some(area);
+        (
+            infer_shrink_every(&record.shrink_turns),
+            record.shrink_turns.last().copied().unwrap_or(0),
+        )
+    });
+    let (inferred, last_shrink) = history.unwrap_or((None, 0));
+
+    let shrink_every = shrink_every_from_settings(game)
+        .or(inferred)
+        .unwrap_or(DEFAULT_SHRINK_EVERY)
+        .max(1);
+    let mut next_shrink = last_shrink + shrink_every;
+    while next_shrink <= *turn {
+        next_shrink += shrink_every;
+    }
+    let forecast = Forecast {
+        turn: *turn,
+        area,
+        shrink_every,
+        next_shrink,
+    };
+    debug!("Royale forecast: {:?}", forecast);
+    Some(forecast)
+}
+
+// Keeps the targets that will still be safe by the time we could reach them,
+// judging arrival by straight line distance. If none will be, they're all
+// returned since we have to go somewhere.
+pub fn safe_targets<'a>(
+    forecast: Option<&Forecast>,
+    head: &BattlesnakeCoord,
+    targets: impl IntoIterator<Item = &'a BattlesnakeCoord>,
+) -> Vec<&'a BattlesnakeCoord> {
+    let targets: Vec<&BattlesnakeCoord> = targets.into_iter().collect();
+    let forecast = match forecast {
+        Some(forecast) => forecast,
+        None => return targets,
+    };
+    let safe: Vec<&BattlesnakeCoord> = targets
+        .iter()
+        .filter(|t| {
+            let eta = (head.x as i32 - t.x as i32).unsigned_abs()
+                + (head.y as i32 - t.y as i32).unsigned_abs();
+            !forecast.is_hazardous_in(t, eta)
+        })
+        .copied()
+        .collect();
+    if safe.is_empty() {
+        targets
+    } else {
+        safe
+    }
+}
+
+fn shrink_every_from_settings(game: &Game) -> Option<u32> {
+    let settings = game.ruleset.get("settings")?;
+    settings
+        .get("royale")
+        .and_then(|r| r.get("shrinkEveryNTurns"))
+        .or_else(|| settings.get("shrinkEveryNTurns"))
+        .and_then(Value::as_u64)
+        .map(|n| n as u32)
+}
+
+// The smallest gap between two shrinks we've seen. Random sides mean a shrink
+// can't be missed, but we might join a game part way through.
+fn infer_shrink_every(shrink_turns: &[u32]) -> Option<u32> {
+    shrink_turns.windows(2).map(|w| w[1] - w[0]).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    use super::observe;
+    use crate::logic::session;
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
+
+    fn board_with_left_columns(columns: u32) -> BattlesnakeBoard {
+        let mut hazards = Vec::new();
+        for x in 0..columns {
+            for y in 0..11 {
+                hazards.push(BattlesnakeCoord { x, y });
+            }
+        }
+        BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards,
+            snakes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn infers_shrinks_from_history() {
+        let game = Game {
+            id: "royale-history".to_string(),
+            ruleset: HashMap::from([("name".to_string(), json!("royale"))]),
+            timeout: 500,
+        };
+        session::begin(&game);
+        observe(&game, &9, &board_with_left_columns(0));
+        observe(&game, &10, &board_with_left_columns(1));
+        observe(&game, &19, &board_with_left_columns(1));
+        let forecast = observe(&game, &20, &board_with_left_columns(2)).unwrap();
+        assert_eq!(forecast.shrink_every, 10);
+        assert_eq!(forecast.next_shrink, 30);
+
+        // the column next to the hazards goes on the next shrink, the middle
+        // of the board takes a lot longer
+        let edge = BattlesnakeCoord { x: 2, y: 5 };
+        let middle = BattlesnakeCoord { x: 6, y: 5 };
+        assert!(!forecast.is_hazardous_in(&edge, 9));
+        assert!(forecast.is_hazardous_in(&edge, 10));
+        assert!(!forecast.is_hazardous_in(&middle, 30));
+        session::finish(&game);
+
+        // a decision that finishes after /end doesn't bring the game back
+        observe(&game, &21, &board_with_left_columns(2));
+        assert_eq!(session::update(&game, |_| ()), None);
+    }
+}
@@ -0,0 +1,204 @@
+// What we remember about a game between requests, keyed by `Game.id`. A
+// session starts on /start, or on the first move of a game we never saw start,
+// is updated every turn and is dropped on /end. Games that never send /end are
+// evicted once they've been quiet for longer than the TTL.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::royale::RoyaleHistory;
+use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
+
+// Far longer than the gap between two moves of a game that's still going
+pub const SESSION_TTL: Duration = Duration::from_secs(600);
+
+lazy_static! {
+    static ref SESSIONS: Mutex<SessionStore> = Mutex::new(SessionStore::new(SESSION_TTL));
+}
+
+// The last turn we answered, as one of our snakes saw it
+#[derive(Debug, Clone)]
+pub struct LastTurn {
+    pub turn: u32,
+    pub board: BattlesnakeBoard,
+    pub chosen: &'static str,
+    // where the path we followed was heading, if we followed one
+    pub goal: Option<BattlesnakeCoord>,
+}
+
+#[derive(Debug, Default)]
+pub struct Session {
+    pub royale: RoyaleHistory,
+    // keyed by snake id, since we can be in the same game more than once
+    pub last_turns: HashMap<String, LastTurn>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    session: Session,
+    touched: Instant,
+}
+
+#[derive(Debug)]
+pub struct SessionStore {
+    sessions: HashMap<String, Entry>,
+    ttl: Duration,
+}
+
+impl SessionStore {
+    pub fn new(ttl: Duration) -> SessionStore {
+        SessionStore {
+            sessions: HashMap::new(),
+            ttl,
+        }
+    }
+
+    // Starts a fresh session, throwing away anything left over under the same id
+    pub fn begin(&mut self, game_id: &str, now: Instant) {
+        self.evict(now);
+        self.sessions.insert(
+            game_id.to_string(),
+            Entry {
+                session: Session::default(),
+                touched: now,
+            },
+        );
+    }
+
+    // Runs `f` on the session for `game_id`, starting one if there isn't one
+    pub fn with<T>(&mut self, game_id: &str, now: Instant, f: impl FnOnce(&mut Session) -> T) -> T {
+        self.evict(now);
+        let entry = self
+            .sessions
+            .entry(game_id.to_string())
+            .or_insert_with(|| Entry {
+                session: Session::default(),
+                touched: now,
+            });
+        entry.touched = now;
+        f(&mut entry.session)
+    }
+
+    // Runs `f` on the session for `game_id` if there is one. Never starts a
+    // session, so nothing that finishes late can bring back a game after /end.
+    pub fn update<T>(
+        &mut self,
+        game_id: &str,
+        now: Instant,
+        f: impl FnOnce(&mut Session) -> T,
+    ) -> Option<T> {
+        self.evict(now);
+        let entry = self.sessions.get_mut(game_id)?;
+        entry.touched = now;
+        Some(f(&mut entry.session))
+    }
+
+    pub fn finish(&mut self, game_id: &str) -> Option<Session> {
+        self.sessions.remove(game_id).map(|entry| entry.session)
+    }
+
+    // Drops every session that hasn't been touched within the TTL
+    pub fn evict(&mut self, now: Instant) {
+        let ttl = self.ttl;
+        self.sessions
+            .retain(|_, entry| now.saturating_duration_since(entry.touched) < ttl);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
+
+pub fn begin(game: &Game) {
+    SESSIONS.lock().unwrap().begin(&game.id, Instant::now());
+}
+
+// Starts a session for a game we never saw start, keeping the one it has if
+// it's already going
+pub fn join(game: &Game) {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .with(&game.id, Instant::now(), |_| ());
+}
+
+// Runs `f` on the game's session, if it still has one. `f` mustn't call back
+// into this module, the store is locked while it runs.
+pub fn update<T>(game: &Game, f: impl FnOnce(&mut Session) -> T) -> Option<T> {
+    SESSIONS.lock().unwrap().update(&game.id, Instant::now(), f)
+}
+
+pub fn record_turn(game: &Game, snake_id: &str, last: LastTurn) {
+    update(game, |session| {
+        session.last_turns.insert(snake_id.to_string(), last);
+    });
+}
+
+// Drops everything we remembered about a game
+pub fn finish(game: &Game) {
+    SESSIONS.lock().unwrap().finish(&game.id);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{LastTurn, SessionStore};
+    use crate::Board as BattlesnakeBoard;
+
+    fn last_turn(turn: u32) -> LastTurn {
+        LastTurn {
+            turn,
+            board: BattlesnakeBoard {
+                width: 11,
+                height: 11,
+                food: Vec::new(),
+                hazards: Vec::new(),
+                snakes: Vec::new(),
+            },
+            chosen: "up",
+            goal: None,
+        }
+    }
+
+    #[test]
+    fn remembers_turns_until_finished_or_stale() {
+        let start = Instant::now();
+        let mut store = SessionStore::new(Duration::from_secs(60));
+        store.begin("a", start);
+        store.with("a", start, |s| {
+            s.last_turns.insert("you".to_string(), last_turn(0))
+        });
+        // a game we never saw start still gets a session
+        store.with("b", start, |s| {
+            s.last_turns.insert("you".to_string(), last_turn(4))
+        });
+        assert_eq!(store.len(), 2);
+        // but only starting one on purpose does
+        assert_eq!(store.update("c", start, |_| ()), None);
+        assert_eq!(store.len(), 2);
+
+        // "a" keeps moving, "b" goes quiet and is evicted
+        let later = start + Duration::from_secs(45);
+        store.with("a", later, |s| {
+            s.last_turns.insert("you".to_string(), last_turn(1))
+        });
+        let stale = start + Duration::from_secs(90);
+        let turn = store.with("a", stale, |s| s.last_turns["you"].turn);
+        assert_eq!(turn, 1);
+        assert_eq!(store.len(), 1);
+
+        // starting over or finishing forgets everything
+        store.begin("a", stale);
+        assert!(store.with("a", stale, |s| s.last_turns.is_empty()));
+        assert!(store.finish("a").is_some());
+        assert!(store.is_empty());
+        assert_eq!(store.update("a", stale, |_| ()), None);
+        assert!(store.is_empty());
+    }
+}
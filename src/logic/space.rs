@@ -0,0 +1,120 @@
+// How much room is left behind each move. A short path to food is no good if it
+// leads into a pocket we can't fit in.
+
+use log::debug;
+use std::collections::{HashSet, VecDeque};
+
+use crate::utils::{self};
+use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveSpace {
+    pub direction: &'static str,
+    pub area: usize,
+}
+
+// For every move from `head` into an open cell, the number of cells we could
+// still reach after making it (including the cell we move into). The cell we
+// are leaving counts as blocked since our neck will be there.
+pub fn reachable_space(
+    pathfinding_board: &PathfindingBoard,
+    board: &BattlesnakeBoard,
+    head: &BattlesnakeCoord,
+) -> Vec<MoveSpace> {
+    let head_pos = utils::coord_to_pos(board, head);
+    let spaces: Vec<MoveSpace> = pathfinding_board
+        .get_successors(&head_pos)
+        .iter()
+        .map(|s| MoveSpace {
+            direction: utils::get_next_move_from_coord(head, &utils::pos_to_coord(board, &s.pos)),
+            area: reachable_area(pathfinding_board, &s.pos, &[head_pos]),
+        })
+        .collect();
+    debug!("Reachable space: {:?}", spaces);
+    spaces
+}
+
+// Flood fills from `start`, treating `blocked` as extra walls
+pub fn reachable_area(
+    pathfinding_board: &PathfindingBoard,
+    start: &PathfindingPos,
+    blocked: &[PathfindingPos],
+) -> usize {
+    let mut seen: HashSet<PathfindingPos> = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(*start);
+    queue.push_back(*start);
+    while let Some(p) = queue.pop_front() {
+        for s in pathfinding_board.get_successors(&p) {
+            if !blocked.contains(&s.pos) && seen.insert(s.pos) {
+                queue.push_back(s.pos);
+            }
+        }
+    }
+    seen.len()
+}
+
+// A move fits if the space behind it can hold our whole body. When no move
+// fits we're going to be squeezed whichever way we go, so nothing is refused.
+pub fn fits(spaces: &[MoveSpace], direction: &str, length: usize) -> bool {
+    if !spaces.iter().any(|s| s.area >= length) {
+        return true;
+    }
+    spaces
+        .iter()
+        .any(|s| s.direction == direction && s.area >= length)
+}
+
+pub fn largest(spaces: &[MoveSpace]) -> Option<&'static str> {
+    spaces.iter().max_by_key(|s| s.area).map(|s| s.direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fits, reachable_space, MoveSpace};
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+    use rust_pathfinding::Board as PathfindingBoard;
+
+    #[test]
+    fn counts_space_behind_each_move() {
+        let board = BattlesnakeBoard {
+            width: 5,
+            height: 5,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: Vec::new(),
+        };
+        // head at (1, 2), a wall cuts off the two cells on the left
+        let pathfinding_board = PathfindingBoard::new(
+            vec![
+                "X1111".to_string(),
+                "X1111".to_string(),
+                "11111".to_string(),
+                "XX111".to_string(),
+                "11111".to_string(),
+            ],
+            false,
+        );
+        let spaces = reachable_space(&pathfinding_board, &board, &BattlesnakeCoord { x: 1, y: 2 });
+        assert_eq!(
+            spaces,
+            vec![
+                MoveSpace {
+                    direction: "left",
+                    area: 1
+                },
+                MoveSpace {
+                    direction: "up",
+                    area: 19
+                },
+                MoveSpace {
+                    direction: "right",
+                    area: 19
+                },
+            ]
+        );
+        assert!(!fits(&spaces, "left", 3));
+        assert!(fits(&spaces, "up", 3));
+    }
+}
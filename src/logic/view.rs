@@ -0,0 +1,63 @@
+// The board as seen by one snake. The engine makes no promise about where we
+// are in `board.snakes`, so we pick ourselves out by id and everyone else is an
+// opponent.
+
+use crate::{Battlesnake, Board as BattlesnakeBoard, GameState};
+
+#[derive(Debug, Clone)]
+pub struct BoardView<'a> {
+    pub board: &'a BattlesnakeBoard,
+    pub you: &'a Battlesnake,
+    pub opponents: Vec<&'a Battlesnake>,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn new(board: &'a BattlesnakeBoard, you: &'a Battlesnake) -> BoardView<'a> {
+        BoardView {
+            board,
+            you,
+            opponents: board.snakes.iter().filter(|s| s.id != you.id).collect(),
+        }
+    }
+
+    pub fn from_state(state: &'a GameState) -> BoardView<'a> {
+        BoardView::new(&state.board, &state.you)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::BoardView;
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Game, GameState};
+
+    #[test]
+    fn finds_us_anywhere_in_the_snake_list() {
+        let state = GameState {
+            game: Game {
+                id: "game".to_string(),
+                ruleset: HashMap::new(),
+                timeout: 500,
+            },
+            turn: 0,
+            board: BattlesnakeBoard {
+                width: 11,
+                height: 11,
+                food: Vec::new(),
+                hazards: Vec::new(),
+                snakes: vec![
+                    snake("a", vec![(1, 0); 3]),
+                    snake("you", vec![(5, 0); 3]),
+                    snake("b", vec![(9, 0); 3]),
+                ],
+            },
+            you: snake("you", vec![(5, 0); 3]),
+        };
+        let view = BoardView::from_state(&state);
+        assert_eq!(view.you.id, "you");
+        let opponents: Vec<&str> = view.opponents.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(opponents, vec!["a", "b"]);
+    }
+}
@@ -1,72 +1,35 @@
 #[macro_use]
 extern crate rocket;
 
-#[macro_use]
-extern crate lazy_static;
-
 use log::info;
 use rocket::fairing::AdHoc;
 use rocket::http::Status;
-use rocket::serde::{json::Json, Deserialize};
-use rurel::AgentTrainer;
-use serde::Serialize;
+use rocket::request::{self, FromRequest, Request};
+use rocket::serde::json::Json;
+use rocket::State;
 use serde_json::Value;
-use std::collections::HashMap;
 use std::env;
-use std::sync::{Arc, Mutex};
-
-mod learning;
-mod logic;
-mod utils;
-
-lazy_static! {
-    static ref AGENT_TRAINER: Arc<Mutex<AgentTrainer<learning::MyState>>> =
-        Arc::new(Mutex::from(AgentTrainer::new()));
-}
-
-// API and Response Objects
-// See https://docs.battlesnake.com/api
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Game {
-    id: String,
-    ruleset: HashMap<String, Value>,
-    timeout: u32,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct Board {
-    height: u32,
-    width: u32,
-    food: Vec<Coord>,
-    snakes: Vec<Battlesnake>,
-    hazards: Vec<Coord>,
-}
-
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Battlesnake {
-    id: String,
-    name: String,
-    health: u32,
-    body: Vec<Coord>,
-    head: Coord,
-    length: u32,
-    latency: String,
-    shout: Option<String>,
-}
-
-#[derive(Deserialize, Serialize, Debug, PartialEq, Clone, Default)]
-pub struct Coord {
-    x: u32,
-    y: u32,
-}
-
-#[derive(Deserialize, Serialize, Debug)]
-pub struct GameState {
-    game: Game,
-    turn: u32,
-    board: Board,
-    you: Battlesnake,
+use std::sync::Arc;
+use std::time::Instant;
+
+use starter_snake_rust::logic::deadline::Deadline;
+use starter_snake_rust::logic::view::BoardView;
+use starter_snake_rust::logic::StrategyConfig;
+use starter_snake_rust::recorder::{Record, RecordQueue};
+use starter_snake_rust::{learning, logic, GameState, AGENT_TRAINER};
+
+// When a request came in, before its body was read and parsed. Set by the
+// "Arrival Time" fairing.
+#[derive(Clone, Copy)]
+struct Arrival(Instant);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Arrival {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, ()> {
+        request::Outcome::Success(*request.local_cache(|| Arrival(Instant::now())))
+    }
 }
 
 #[get("/")]
@@ -75,32 +38,65 @@ fn handle_index() -> Json<Value> {
 }
 
 #[post("/start", format = "json", data = "<start_req>")]
-fn handle_start(start_req: Json<GameState>) -> Status {
+fn handle_start(records: &State<RecordQueue>, start_req: Json<GameState>) -> Status {
     logic::start(
         &start_req.game,
         &start_req.turn,
         &start_req.board,
         &start_req.you,
     );
+    records.push(Record {
+        event: "start".to_string(),
+        request: start_req.into_inner(),
+        personality: format!("{:?}", StrategyConfig::default().personality),
+        chosen: None,
+        mode: None,
+        compute_ms: None,
+        path: None,
+    });
 
     Status::Ok
 }
 
 #[post("/move", format = "json", data = "<move_req>")]
-fn handle_move(move_req: Json<GameState>) -> Json<Value> {
-    let response = logic::get_move(
-        &move_req.game,
-        &move_req.turn,
-        &move_req.board,
-        &move_req.you,
-    );
+fn handle_move(
+    arrival: Arrival,
+    records: &State<RecordQueue>,
+    move_req: Json<GameState>,
+) -> Json<Value> {
+    let started = arrival.0;
+    let deadline = Deadline::since(started, &move_req.game);
+    let config = StrategyConfig::default();
+    let view = BoardView::from_state(&move_req);
+    let decided = logic::get_move(&move_req.game, &move_req.turn, &view, &config, &deadline);
+    let compute_ms = started.elapsed().as_millis() as u64;
+    let response = decided.response();
+
+    records.push(Record {
+        event: "move".to_string(),
+        request: move_req.into_inner(),
+        personality: format!("{:?}", config.personality),
+        chosen: Some(decided.chosen.to_string()),
+        mode: decided.mode.map(|mode| format!("{:?}", mode)),
+        compute_ms: Some(compute_ms),
+        path: Some(decided.path),
+    });
 
     Json(response)
 }
 
 #[post("/end", format = "json", data = "<end_req>")]
-fn handle_end(end_req: Json<GameState>) -> Status {
+fn handle_end(records: &State<RecordQueue>, end_req: Json<GameState>) -> Status {
     logic::end(&end_req.game, &end_req.turn, &end_req.board, &end_req.you);
+    records.push(Record {
+        event: "end".to_string(),
+        request: end_req.into_inner(),
+        personality: format!("{:?}", StrategyConfig::default().personality),
+        chosen: None,
+        mode: None,
+        compute_ms: None,
+        path: None,
+    });
 
     Status::Ok
 }
@@ -131,6 +127,14 @@ fn rocket() -> _ {
     info!("Starting Battlesnake Server...");
 
     rocket::build()
+        // requests are written to RECORD_DIR off the request threads, if it's set
+        .manage(RecordQueue::from_env())
+        // the move deadline counts from here, parsing the request eats into it
+        .attach(AdHoc::on_request("Arrival Time", |req, _| {
+            Box::pin(async move {
+                req.local_cache(|| Arrival(Instant::now()));
+            })
+        }))
         .attach(AdHoc::on_response("Server ID Middleware", |_, res| {
             Box::pin(async move {
                 res.set_raw_header("Server", "battlesnake/rust-friday-battlesnakes");
@@ -0,0 +1,294 @@
+// Keeps a copy of every request we get, one JSON line each, so real games can
+// be replayed offline. Nothing is recorded unless `RECORD_DIR` is set, and each
+// game gets its own `<RECORD_DIR>/<game id>.jsonl`.
+
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::{Coord as BattlesnakeCoord, GameState};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Record {
+    // "start", "move" or "end"
+    pub event: String,
+    pub request: GameState,
+    pub personality: String,
+    // only for moves
+    #[serde(rename = "move")]
+    pub chosen: Option<String>,
+    pub mode: Option<String>,
+    pub compute_ms: Option<u64>,
+    // the path the move followed, or Some(None) when it didn't follow one. None
+    // only in recordings made before paths were kept.
+    #[serde(default, deserialize_with = "recorded")]
+    pub path: Option<Option<Vec<BattlesnakeCoord>>>,
+}
+
+// Tells a field that was written as null apart from one that wasn't written
+fn recorded<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    dir: PathBuf,
+}
+
+impl Recorder {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Recorder {
+        Recorder { dir: dir.into() }
+    }
+
+    // Records into `RECORD_DIR`, or not at all if it isn't set
+    pub fn from_env() -> Option<Recorder> {
+        env::var("RECORD_DIR")
+            .ok()
+            .filter(|dir| !dir.is_empty())
+            .map(Recorder::new)
+    }
+
+    pub fn path_for(&self, game_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", file_stem(game_id)))
+    }
+
+    // A failed write is logged and otherwise ignored, it must never cost us the
+    // game
+    pub fn record(&self, record: &Record) {
+        if let Err(e) = self.append(record) {
+            warn!(
+                "Couldn't record {} for game {}: {}",
+                record.event, record.request.game.id, e
+            );
+        }
+    }
+
+    fn append(&self, record: &Record) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path_for(&record.request.game.id))?;
+        // one write per line so concurrent requests can't interleave
+        file.write_all(line.as_bytes())
+    }
+}
+
+// Hands records over to a thread that writes them, so a slow disk can't eat
+// into the time we have to answer a move
+#[derive(Debug)]
+pub struct RecordQueue {
+    sender: Option<Sender<Record>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl RecordQueue {
+    // Writes with `recorder`, or drops every record when there's no recorder
+    pub fn spawn(recorder: Option<Recorder>) -> RecordQueue {
+        let recorder = match recorder {
+            Some(recorder) => recorder,
+            None => {
+                return RecordQueue {
+                    sender: None,
+                    writer: None,
+                }
+            }
+        };
+        let (sender, receiver) = mpsc::channel::<Record>();
+        let writer = thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || {
+                for record in receiver {
+                    recorder.record(&record);
+                }
+            })
+            .expect("spawn recorder thread");
+        RecordQueue {
+            sender: Some(sender),
+            writer: Some(writer),
+        }
+    }
+
+    pub fn from_env() -> RecordQueue {
+        RecordQueue::spawn(Recorder::from_env())
+    }
+
+    pub fn push(&self, record: Record) {
+        if let Some(sender) = &self.sender {
+            if sender.send(record).is_err() {
+                warn!("The recorder thread is gone, dropping a record");
+            }
+        }
+    }
+
+    // Waits for everything queued so far to be written
+    pub fn finish(mut self) {
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+// A file name for anything to do with `game_id`. Game ids come from the engine,
+// so don't let one reach outside the directory.
+pub fn file_stem(game_id: &str) -> String {
+    game_id
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+// A recorded request, or a bare move payload with nothing to compare against
+pub fn parse_line(line: &str) -> Result<Record, serde_json::Error> {
+    serde_json::from_str::<Record>(line).or_else(|_| {
+        serde_json::from_str::<GameState>(line).map(|request| Record {
+            event: "move".to_string(),
+            request,
+            personality: String::new(),
+            chosen: None,
+            mode: None,
+            compute_ms: None,
+            path: None,
+        })
+    })
+}
+
+// Every record in a file written by `Recorder`, or in a file of bare move
+// payloads. Lines that don't parse are skipped with a warning.
+pub fn read_records(path: &Path) -> io::Result<Vec<Record>> {
+    let file = fs::File::open(path)?;
+    let mut records = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => warn!("{}:{}: {}", path.display(), i + 1, e),
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+
+    use super::{parse_line, read_records, Record, RecordQueue, Recorder};
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game, GameState};
+
+    fn request(game_id: &str, turn: u32) -> GameState {
+        let you = snake("you", vec![(1, 1); 3]);
+        GameState {
+            game: Game {
+                id: game_id.to_string(),
+                ruleset: HashMap::new(),
+                timeout: 500,
+            },
+            turn,
+            board: BattlesnakeBoard {
+                width: 11,
+                height: 11,
+                food: Vec::new(),
+                hazards: Vec::new(),
+                snakes: vec![you.clone()],
+            },
+            you,
+        }
+    }
+
+    #[test]
+    fn appends_one_line_per_request() {
+        let dir = env::temp_dir().join(format!("recorder-test-{}", std::process::id()));
+        let recorder = Recorder::new(&dir);
+        let queue = RecordQueue::spawn(Some(recorder.clone()));
+        let game_id = "../game/1";
+        for (event, turn) in &[("start", 0), ("move", 0), ("move", 1), ("end", 2)] {
+            queue.push(Record {
+                event: event.to_string(),
+                request: request(game_id, *turn),
+                personality: "HeadHunter".to_string(),
+                chosen: Some("up".to_string()).filter(|_| *event == "move"),
+                mode: None,
+                compute_ms: Some(3).filter(|_| *event == "move"),
+                path: Some(None),
+            });
+        }
+        queue.finish();
+
+        let path = recorder.path_for(game_id);
+        assert_eq!(path, dir.join("___game_1.jsonl"));
+        let records = read_records(&path).unwrap();
+        let events: Vec<(&str, u32)> = records
+            .iter()
+            .map(|r| (r.event.as_str(), r.request.turn))
+            .collect();
+        assert_eq!(
+            events,
+            vec![("start", 0), ("move", 0), ("move", 1), ("end", 2)]
+        );
+        assert_eq!(records[1].chosen.as_deref(), Some("up"));
+        assert_eq!(records[3].chosen, None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parses_records_and_bare_requests() {
+        let record = Record {
+            event: "move".to_string(),
+            request: request("game", 3),
+            personality: "Snacky".to_string(),
+            chosen: Some("left".to_string()),
+            mode: Some("Eat".to_string()),
+            compute_ms: Some(12),
+            path: Some(Some(vec![
+                BattlesnakeCoord { x: 1, y: 1 },
+                BattlesnakeCoord { x: 0, y: 1 },
+            ])),
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        let parsed = parse_line(&line).unwrap();
+        assert_eq!(parsed.personality, "Snacky");
+        assert_eq!(parsed.chosen.as_deref(), Some("left"));
+        assert_eq!(parsed.mode.as_deref(), Some("Eat"));
+        assert_eq!(parsed.path, record.path);
+
+        // moves that followed no path, and ones recorded before paths were
+        let no_path = Record {
+            path: Some(None),
+            ..parse_line(&line).unwrap()
+        };
+        let no_path = parse_line(&serde_json::to_string(&no_path).unwrap()).unwrap();
+        assert_eq!(no_path.path, Some(None));
+        let old = line.replace(r#","path":[{"x":1,"y":1},{"x":0,"y":1}]"#, "");
+        assert_ne!(old, line);
+        assert_eq!(parse_line(&old).unwrap().path, None);
+
+        // a plain move payload is a move with nothing recorded about it
+        let bare = parse_line(&serde_json::to_string(&request("game", 4)).unwrap()).unwrap();
+        assert_eq!((bare.event.as_str(), bare.request.turn), ("move", 4));
+        assert_eq!(bare.personality, "");
+        assert_eq!(bare.chosen, None);
+
+        assert!(parse_line("{\"turn\": 1}").is_err());
+    }
+}
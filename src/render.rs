@@ -0,0 +1,291 @@
+// Draws a whole board to a PNG: every snake in its own colour with its head and
+// tail marked, food, hazards, and the path we're planning to take. One image a
+// turn makes a bad move a lot easier to spot.
+
+pub mod terminal;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, ImageResult, Rgb, RgbImage};
+use imageproc::drawing::{
+    draw_filled_circle_mut, draw_filled_rect_mut, draw_line_segment_mut, draw_text_mut,
+};
+use imageproc::rect::Rect;
+use rusttype::{Font, Scale};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::recorder;
+use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+
+pub const CELL_SIZE: u32 = 40;
+// room above the board for the turn number
+const HEADER: u32 = 24;
+
+const BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+const GRID: Rgb<u8> = Rgb([210, 210, 210]);
+const HAZARD: Rgb<u8> = Rgb([90, 90, 90]);
+const FOOD: Rgb<u8> = Rgb([230, 40, 40]);
+const PATH: Rgb<u8> = Rgb([20, 20, 20]);
+const TEXT: Rgb<u8> = Rgb([0, 0, 0]);
+
+// handed out in board order, so a snake keeps its colour for the whole game
+const SNAKE_COLOURS: [Rgb<u8>; 8] = [
+    Rgb([30, 144, 255]),
+    Rgb([50, 205, 50]),
+    Rgb([255, 140, 0]),
+    Rgb([148, 0, 211]),
+    Rgb([0, 206, 209]),
+    Rgb([255, 20, 147]),
+    Rgb([139, 69, 19]),
+    Rgb([128, 128, 0]),
+];
+
+pub fn snake_colour(index: usize) -> Rgb<u8> {
+    SNAKE_COLOURS[index % SNAKE_COLOURS.len()]
+}
+
+fn darken(colour: Rgb<u8>) -> Rgb<u8> {
+    Rgb([colour[0] / 2, colour[1] / 2, colour[2] / 2])
+}
+
+// Top left pixel of a cell. Battlesnake's y goes up, the image's goes down.
+fn cell_origin(board: &BattlesnakeBoard, c: &BattlesnakeCoord) -> (i32, i32) {
+    let row = board.height - 1 - c.y;
+    ((c.x * CELL_SIZE) as i32, (HEADER + row * CELL_SIZE) as i32)
+}
+
+fn cell_centre(board: &BattlesnakeBoard, c: &BattlesnakeCoord) -> (i32, i32) {
+    let (x, y) = cell_origin(board, c);
+    let half = (CELL_SIZE / 2) as i32;
+    (x + half, y + half)
+}
+
+// A square `inset` pixels in from each side of the cell
+fn fill_cell(
+    image: &mut RgbImage,
+    board: &BattlesnakeBoard,
+    c: &BattlesnakeCoord,
+    inset: u32,
+    colour: Rgb<u8>,
+) {
+    let (x, y) = cell_origin(board, c);
+    let size = CELL_SIZE - 2 * inset;
+    let rect = Rect::at(x + inset as i32, y + inset as i32).of_size(size, size);
+    draw_filled_rect_mut(image, rect, colour);
+}
+
+// Hazards are mixed into whatever is already in the cell so it still shows
+fn shade_cell(image: &mut RgbImage, board: &BattlesnakeBoard, c: &BattlesnakeCoord) {
+    let (x, y) = cell_origin(board, c);
+    for py in y..y + CELL_SIZE as i32 {
+        for px in x..x + CELL_SIZE as i32 {
+            let pixel = image.get_pixel_mut(px as u32, py as u32);
+            for (channel, hazard) in pixel.0.iter_mut().zip(HAZARD.0.iter()) {
+                *channel = ((*channel as u16 + *hazard as u16) / 2) as u8;
+            }
+        }
+    }
+}
+
+fn adjacent(a: &BattlesnakeCoord, b: &BattlesnakeCoord) -> bool {
+    let dx = (a.x as i32 - b.x as i32).abs();
+    let dy = (a.y as i32 - b.y as i32).abs();
+    dx + dy == 1
+}
+
+// Joins two neighbouring segments so a body reads as one snake. Segments on
+// opposite edges of a wrapped board aren't joined.
+fn join_cells(
+    image: &mut RgbImage,
+    board: &BattlesnakeBoard,
+    a: &BattlesnakeCoord,
+    b: &BattlesnakeCoord,
+    inset: u32,
+    colour: Rgb<u8>,
+) {
+    if !adjacent(a, b) {
+        return;
+    }
+    let (ax, ay) = cell_centre(board, a);
+    let (bx, by) = cell_centre(board, b);
+    let half = (CELL_SIZE / 2 - inset) as i32;
+    let (left, top) = (ax.min(bx) - half, ay.min(by) - half);
+    let width = ((ax - bx).abs() + 2 * half) as u32;
+    let height = ((ay - by).abs() + 2 * half) as u32;
+    draw_filled_rect_mut(image, Rect::at(left, top).of_size(width, height), colour);
+}
+
+pub fn render(board: &BattlesnakeBoard, turn: u32, path: Option<&[BattlesnakeCoord]>) -> RgbImage {
+    render_captioned(board, &format!("turn {}", turn), path)
+}
+
+// `render` with any text in the header instead of just the turn
+pub fn render_captioned(
+    board: &BattlesnakeBoard,
+    caption: &str,
+    path: Option<&[BattlesnakeCoord]>,
+) -> RgbImage {
+    let mut image = RgbImage::from_pixel(
+        board.width * CELL_SIZE,
+        HEADER + board.height * CELL_SIZE,
+        BACKGROUND,
+    );
+
+    let font = Font::try_from_bytes(include_bytes!(
+        "../vendor/rust-pathfinding/src/DejaVuSans.ttf"
+    ))
+    .expect("bundled font");
+    draw_text_mut(&mut image, TEXT, 4, 2, Scale::uniform(20.0), &font, caption);
+
+    for i in 0..=board.width {
+        let x = (i * CELL_SIZE).min(board.width * CELL_SIZE - 1) as f32;
+        let bottom = (HEADER + board.height * CELL_SIZE) as f32;
+        draw_line_segment_mut(&mut image, (x, HEADER as f32), (x, bottom), GRID);
+    }
+    for i in 0..=board.height {
+        let y = (HEADER + i * CELL_SIZE).min(HEADER + board.height * CELL_SIZE - 1) as f32;
+        let right = (board.width * CELL_SIZE) as f32;
+        draw_line_segment_mut(&mut image, (0.0, y), (right, y), GRID);
+    }
+
+    for f in &board.food {
+        draw_filled_circle_mut(
+            &mut image,
+            cell_centre(board, f),
+            (CELL_SIZE / 4) as i32,
+            FOOD,
+        );
+    }
+
+    for (i, snake) in board.snakes.iter().enumerate() {
+        let colour = snake_colour(i);
+        for (segment, next) in snake.body.iter().zip(snake.body.iter().skip(1)) {
+            join_cells(&mut image, board, segment, next, 8, colour);
+        }
+        for segment in &snake.body {
+            fill_cell(&mut image, board, segment, 6, colour);
+        }
+        // the tail is a dot, the head a darker, full size square with an eye
+        if let Some(tail) = snake.body.last() {
+            fill_cell(&mut image, board, tail, 6, BACKGROUND);
+            draw_filled_circle_mut(
+                &mut image,
+                cell_centre(board, tail),
+                (CELL_SIZE / 5) as i32,
+                colour,
+            );
+        }
+        fill_cell(&mut image, board, &snake.head, 3, darken(colour));
+        draw_filled_circle_mut(&mut image, cell_centre(board, &snake.head), 4, BACKGROUND);
+    }
+
+    for h in &board.hazards {
+        shade_cell(&mut image, board, h);
+    }
+
+    if let Some(path) = path {
+        for (a, b) in path.iter().zip(path.iter().skip(1)) {
+            if !adjacent(a, b) {
+                continue;
+            }
+            let (ax, ay) = cell_centre(board, a);
+            let (bx, by) = cell_centre(board, b);
+            // three pixels wide so it stands out over the snakes
+            for offset in -1..=1 {
+                let (ox, oy) = if ax == bx { (offset, 0) } else { (0, offset) };
+                draw_line_segment_mut(
+                    &mut image,
+                    ((ax + ox) as f32, (ay + oy) as f32),
+                    ((bx + ox) as f32, (by + oy) as f32),
+                    PATH,
+                );
+            }
+        }
+        if let Some(goal) = path.last() {
+            draw_filled_circle_mut(&mut image, cell_centre(board, goal), 5, PATH);
+        }
+    }
+
+    image
+}
+
+// Where the image for `turn` of `game_id` goes: `<dir>/<game id>/<turn>.png`,
+// with the turn padded so the files sort in order
+pub fn turn_path(dir: &Path, game_id: &str, turn: u32) -> PathBuf {
+    dir.join(recorder::file_stem(game_id))
+        .join(format!("{:04}.png", turn))
+}
+
+pub fn save(image: &RgbImage, dir: &Path, game_id: &str, turn: u32) -> ImageResult<PathBuf> {
+    let path = turn_path(dir, game_id, turn);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    image.save(&path)?;
+    Ok(path)
+}
+
+// Writes `frames` as one looping animation, each shown for `delay_ms`
+pub fn save_gif(frames: &[RgbImage], path: &Path, delay_ms: u32) -> ImageResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut encoder = GifEncoder::new_with_speed(fs::File::create(path)?, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(delay_ms, 1);
+    encoder.encode_frames(frames.iter().map(|frame| {
+        let rgba = DynamicImage::ImageRgb8(frame.clone()).into_rgba8();
+        Frame::from_parts(rgba, 0, 0, delay)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::{cell_centre, render, snake_colour, CELL_SIZE, FOOD, HEADER};
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+
+    #[test]
+    fn draws_food_snakes_and_path() {
+        let board = BattlesnakeBoard {
+            width: 5,
+            height: 4,
+            food: vec![BattlesnakeCoord { x: 4, y: 3 }],
+            hazards: vec![BattlesnakeCoord { x: 0, y: 3 }],
+            snakes: vec![
+                snake("you", vec![(1, 1), (1, 0), (2, 0)]),
+                snake("them", vec![(3, 2), (3, 1), (4, 1)]),
+            ],
+        };
+        let path = vec![
+            BattlesnakeCoord { x: 1, y: 1 },
+            BattlesnakeCoord { x: 1, y: 2 },
+        ];
+        let image = render(&board, 7, Some(&path));
+        assert_eq!(image.dimensions(), (5 * CELL_SIZE, HEADER + 4 * CELL_SIZE));
+
+        let pixel = |c: BattlesnakeCoord, dx: i32, dy: i32| {
+            let (x, y) = cell_centre(&board, &c);
+            *image.get_pixel((x + dx) as u32, (y + dy) as u32)
+        };
+        assert_eq!(pixel(BattlesnakeCoord { x: 4, y: 3 }, 0, 0), FOOD);
+        // off the eye, on the body segment behind the head
+        assert_eq!(
+            pixel(BattlesnakeCoord { x: 1, y: 0 }, 10, 10),
+            snake_colour(0)
+        );
+        assert_eq!(
+            pixel(BattlesnakeCoord { x: 3, y: 1 }, 10, 10),
+            snake_colour(1)
+        );
+        // the path runs up out of our head
+        assert_eq!(
+            pixel(BattlesnakeCoord { x: 1, y: 2 }, 0, 10),
+            Rgb([20, 20, 20])
+        );
+        // hazards darken the cell
+        assert!(pixel(BattlesnakeCoord { x: 0, y: 3 }, 0, 0)[0] < 200);
+    }
+}
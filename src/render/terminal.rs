@@ -0,0 +1,147 @@
+// The same picture as the PNGs, as text: one glyph a cell with the head
+// pointing the way the snake last moved, and a line for each snake underneath.
+// Colour is optional so the output can also go into logs and tests.
+
+use crate::utils;
+use crate::{Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+
+const EMPTY: char = '·';
+const HAZARD: char = '░';
+const FOOD: char = '●';
+const BODY: char = '■';
+const TAIL: char = '□';
+// before a snake has moved its whole body is stacked on the head
+const HEAD: char = '◆';
+
+// ANSI foreground colours handed out in board order, like `render::snake_colour`
+const SNAKE_COLOURS: [u8; 8] = [94, 92, 33, 35, 96, 95, 31, 93];
+const FOOD_COLOUR: u8 = 91;
+const HAZARD_BACKGROUND: u8 = 100;
+
+fn head_glyph(snake: &Battlesnake) -> char {
+    let neck = match snake.body.get(1) {
+        Some(neck) if *neck != snake.head => neck,
+        _ => return HEAD,
+    };
+    match utils::get_next_move_from_coord(neck, &snake.head) {
+        "up" => '▲',
+        "down" => '▼',
+        "left" => '◀',
+        _ => '▶',
+    }
+}
+
+// What's drawn in a cell and, if it belongs to a snake, which one
+fn cell(board: &BattlesnakeBoard, c: &BattlesnakeCoord) -> (char, Option<usize>) {
+    for (i, snake) in board.snakes.iter().enumerate() {
+        if snake.head == *c {
+            return (head_glyph(snake), Some(i));
+        }
+    }
+    for (i, snake) in board.snakes.iter().enumerate() {
+        if let Some(pos) = snake.body.iter().position(|b| b == c) {
+            let glyph = if pos == snake.body.len() - 1 {
+                TAIL
+            } else {
+                BODY
+            };
+            return (glyph, Some(i));
+        }
+    }
+    if board.food.contains(c) {
+        return (FOOD, None);
+    }
+    if board.hazards.contains(c) {
+        return (HAZARD, None);
+    }
+    (EMPTY, None)
+}
+
+fn paint(glyph: char, foreground: Option<u8>, hazard: bool) -> String {
+    let mut codes = Vec::new();
+    if let Some(foreground) = foreground {
+        codes.push(foreground.to_string());
+    }
+    if hazard {
+        codes.push(HAZARD_BACKGROUND.to_string());
+    }
+    if codes.is_empty() {
+        glyph.to_string()
+    } else {
+        format!("\x1b[{}m{}\x1b[0m", codes.join(";"), glyph)
+    }
+}
+
+// The board top row first, followed by a line per snake. Cells are separated by
+// a space so the board comes out roughly square.
+pub fn lines(board: &BattlesnakeBoard, colour: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    for y in (0..board.height).rev() {
+        let row: Vec<String> = (0..board.width)
+            .map(|x| {
+                let c = BattlesnakeCoord { x, y };
+                let (glyph, snake) = cell(board, &c);
+                if !colour {
+                    return glyph.to_string();
+                }
+                let foreground = match snake {
+                    Some(i) => Some(SNAKE_COLOURS[i % SNAKE_COLOURS.len()]),
+                    None if glyph == FOOD => Some(FOOD_COLOUR),
+                    None => None,
+                };
+                paint(glyph, foreground, board.hazards.contains(&c))
+            })
+            .collect();
+        lines.push(row.join(" "));
+    }
+    for (i, snake) in board.snakes.iter().enumerate() {
+        let glyph = if colour {
+            paint(BODY, Some(SNAKE_COLOURS[i % SNAKE_COLOURS.len()]), false)
+        } else {
+            BODY.to_string()
+        };
+        lines.push(format!(
+            "{} {}: {} health, {} long",
+            glyph, snake.name, snake.health, snake.length
+        ));
+    }
+    lines
+}
+
+pub fn render(board: &BattlesnakeBoard, colour: bool) -> String {
+    lines(board, colour).join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lines, render};
+    use crate::test_support::snake;
+    use crate::{Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+
+    #[test]
+    fn draws_heads_bodies_tails_food_and_hazards() {
+        let board = BattlesnakeBoard {
+            width: 5,
+            height: 3,
+            food: vec![BattlesnakeCoord { x: 4, y: 2 }],
+            hazards: vec![BattlesnakeCoord { x: 0, y: 2 }],
+            snakes: vec![
+                snake("you", vec![(1, 1), (1, 0), (2, 0)]),
+                snake("them", vec![(3, 1), (4, 1), (4, 0)]),
+            ],
+        };
+        assert_eq!(
+            lines(&board, false),
+            vec![
+                "░ · · · ●",
+                "· ▲ · ◀ ■",
+                "· ■ □ · □",
+                "■ you: 90 health, 3 long",
+                "■ them: 90 health, 3 long",
+            ]
+        );
+        let coloured = render(&board, true);
+        assert!(coloured.contains("\x1b[94m▲\x1b[0m"));
+        assert!(coloured.contains("\x1b[100m░\x1b[0m"));
+    }
+}
@@ -1,20 +1,32 @@
-use crate::{Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord};
+use crate::{Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game};
 use itertools::Itertools;
+use log::{debug, log_enabled, Level};
 use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
 
+use crate::engine::{self, GameMode, Rules, MOVES};
+use crate::logic::collision::CollisionRisk;
+use crate::logic::view::BoardView;
 use crate::logic::{SnakeMode, SnakePersonality};
+use crate::render::terminal;
 
+// Timid only goes looking for food once its health drops this low
+pub const TIMID_STARVING_HEALTH: u32 = 25;
+
+// A jump of more than one cell can only happen by wrapping around the edge,
+// so it means the opposite direction
 pub fn get_next_move_from_coord(me: &BattlesnakeCoord, next: &BattlesnakeCoord) -> &'static str {
     if me.y == next.y {
-        if me.x as i32 - next.x as i32 == -1 {
+        let dx = next.x as i32 - me.x as i32;
+        if dx == 1 || dx < -1 {
             return "right";
         }
-        return "left";
+        "left"
     } else {
-        if me.y as i32 - next.y as i32 == -1 {
+        let dy = next.y as i32 - me.y as i32;
+        if dy == 1 || dy < -1 {
             return "up";
         }
-        return "down";
+        "down"
     }
 }
 
@@ -26,10 +38,43 @@ pub fn coord_distance(a: &BattlesnakeCoord, b: &BattlesnakeCoord) -> f64 {
     f64::sqrt((b0 - a0).pow(2) as f64 + (b1 - a1).pow(2) as f64)
 }
 
+// Straight line distance, going the short way around the board in wrapped games
+pub fn get_distance(
+    game_mode: GameMode,
+    board: &BattlesnakeBoard,
+    a: &BattlesnakeCoord,
+    b: &BattlesnakeCoord,
+) -> f64 {
+    if !game_mode.wraps() {
+        return coord_distance(a, b);
+    }
+    let dx = (a.x as i32 - b.x as i32).unsigned_abs();
+    let dy = (a.y as i32 - b.y as i32).unsigned_abs();
+    let dx = dx.min(board.width - dx);
+    let dy = dy.min(board.height - dy);
+    f64::sqrt((dx * dx + dy * dy) as f64)
+}
+
+// Whether the snake's tail moves out of the way next turn. It only stays put
+// if the snake just ate, which shows up as the last two segments being stacked.
+pub fn tail_moves(snake: &Battlesnake) -> bool {
+    let len = snake.body.len();
+    len >= 2 && snake.body[len - 1] != snake.body[len - 2]
+}
+
 pub fn get_target_body_from_personality<'a>(
     snake: &'a Battlesnake,
     personality: &SnakePersonality,
+    game_mode: GameMode,
 ) -> Vec<&'a BattlesnakeCoord> {
+    if game_mode == GameMode::Constrictor {
+        // nothing ever moves out of the way in constrictor
+        return snake
+            .body
+            .iter()
+            .unique_by(|f| format!("{}_{}", f.x, f.y))
+            .collect();
+    }
     let avoid = match personality {
         SnakePersonality::HeadHunter => {
             let body = &snake.body[1..];
@@ -39,28 +84,35 @@ pub fn get_target_body_from_personality<'a>(
                 .collect();
             unique_body
         }
+        SnakePersonality::Hungry => {
+            let body = if tail_moves(snake) {
+                &snake.body[..snake.body.len() - 1]
+            } else {
+                &snake.body[..]
+            };
+            body.iter()
+                .unique_by(|f| format!("{}_{}", f.x, f.y))
+                .collect()
+        }
         _ => {
-            let a = snake.body.iter().map(|f| f).collect();
+            let a = snake.body.iter().collect();
             return a;
         }
     };
     avoid
 }
 
-pub fn get_snake_mode(
-    board: &BattlesnakeBoard,
-    snake: &Battlesnake,
-    personality: &SnakePersonality,
-) -> SnakeMode {
-    match personality {
-        &SnakePersonality::HeadHunter => {
+pub fn get_snake_mode(view: &BoardView, personality: &SnakePersonality) -> SnakeMode {
+    let snake = view.you;
+    match *personality {
+        SnakePersonality::HeadHunter => {
             let mut largest_snake = 0;
-            for s in &board.snakes[1..] {
+            for s in &view.opponents {
                 if s.body.len() > largest_snake {
                     largest_snake = s.body.len();
                 }
             }
-            println!(
+            debug!(
                 "Our Size: {}, Largest Snake Size: {}",
                 snake.body.len(),
                 largest_snake
@@ -71,47 +123,162 @@ pub fn get_snake_mode(
                 SnakeMode::Eat
             }
         }
+        SnakePersonality::Timid => {
+            let has_enemies = !view.opponents.is_empty();
+            debug!("Our Health: {}", snake.health);
+            if has_enemies && snake.health > TIMID_STARVING_HEALTH {
+                SnakeMode::Hide
+            } else {
+                SnakeMode::Eat
+            }
+        }
         _ => SnakeMode::Eat,
     }
 }
 
+// Every cell an enemy head could move into next turn
+pub fn get_enemy_next_moves(game_mode: GameMode, view: &BoardView) -> Vec<BattlesnakeCoord> {
+    let mut cells = Vec::new();
+    for snake in &view.opponents {
+        for direction in MOVES {
+            if let Some(c) = engine::step(game_mode, view.board, &snake.head, direction) {
+                if !cells.contains(&c) {
+                    cells.push(c);
+                }
+            }
+        }
+    }
+    cells
+}
+
+// The most a hazard we can survive costs to step onto, for damage that leaves
+// us with a single point of health
+pub const MAX_HAZARD_COST: u32 = 32;
+
+// What it costs us to step onto a cell `stacked` hazards deep: the share of our
+// health it takes, scaled up to `MAX_HAZARD_COST`, so every extra hazard in the
+// stack costs more. None means the damage would kill us, so the cell is as good
+// as a wall.
+pub fn get_hazard_cost(hazard_damage: u32, stacked: u32, health: u32) -> Option<u32> {
+    let damage = hazard_damage * stacked;
+    if damage == 0 {
+        return Some(1);
+    }
+    // moving costs one health on top of the hazard damage
+    if health <= damage + 1 {
+        return None;
+    }
+    let share = damage as f64 / health as f64;
+    Some(1 + ((MAX_HAZARD_COST - 1) as f64 * share).ceil() as u32)
+}
+
 pub fn build_pathfinding_board_with_hazards(
     personality: &SnakePersonality,
-    board: &BattlesnakeBoard,
-    _me: &Battlesnake,
-) -> (PathfindingBoard, Vec<String>) {
-    let mut string_board: Vec<String> = Vec::new();
+    game: &Game,
+    view: &BoardView,
+) -> PathfindingBoard {
+    let (board, me) = (view.board, view.you);
+    let rules = Rules::from_game(game);
     let all_snakes = &board.snakes;
-    for row in 0..board.height {
-        let mut row_string = "".to_string();
-        for col in 0..board.width {
-            let mut found_body = false;
-            for snake in all_snakes {
-                if !found_body {
-                    let avoid = get_target_body_from_personality(snake, personality);
-                    for b in avoid.iter() {
-                        let converted = coord_to_pos(board, b);
-                        let x = converted.0 as u32;
-                        let y = converted.1 as u32;
-                        if row == y && col == x {
-                            row_string += "X";
-                            found_body = true;
-                        }
-                    }
+    let cell_cost = |coord: &BattlesnakeCoord| {
+        let stacked = if board.food.contains(coord) {
+            // eating cancels out the hazard damage
+            0
+        } else {
+            board.hazards.iter().filter(|h| *h == coord).count() as u32
+        };
+        get_hazard_cost(rules.hazard_damage_per_turn, stacked, me.health)
+    };
+    // timid snakes stay away from anywhere another snake could move next, for
+    // the whole path
+    let threatened = if *personality == SnakePersonality::Timid {
+        get_enemy_next_moves(rules.mode, view)
+    } else {
+        Vec::new()
+    };
+    // never step somewhere a snake our size or bigger could also move into. Only
+    // our next step matters, they'll have moved on by the time we get further.
+    let risk = CollisionRisk::from_view(rules.mode, view);
+    let head_to_head: Vec<BattlesnakeCoord> = MOVES
+        .iter()
+        .filter_map(|direction| engine::step(rules.mode, board, &me.head, direction))
+        .filter(|c| risk.is_deadly(c) && !threatened.contains(c))
+        .collect();
+
+    let mut pathfinding_board =
+        PathfindingBoard::filled(board.width as u8, board.height as u8, 1, false)
+            .with_wrapping(rules.mode.wraps());
+    for h in &board.hazards {
+        pathfinding_board.set_cost(&coord_to_pos(board, h), cell_cost(h));
+    }
+    for c in threatened.iter().chain(head_to_head.iter()) {
+        pathfinding_board.set_cost(&coord_to_pos(board, c), None);
+    }
+    for snake in all_snakes {
+        for b in get_target_body_from_personality(snake, personality, rules.mode) {
+            pathfinding_board.set_cost(&coord_to_pos(board, b), None);
+        }
+    }
+    if log_enabled!(Level::Debug) {
+        for line in terminal::lines(board, false) {
+            debug!("{}", line);
+        }
+    }
+
+    // segment i of a snake is gone once the tail has moved up past it, so a
+    // path that gets there late enough can go through. A snake that could eat
+    // next turn keeps its tail where it is for a turn, so everything behind its
+    // head takes one turn longer. Constrictor bodies never move out of the way.
+    if rules.mode != GameMode::Constrictor {
+        for snake in all_snakes {
+            let length =
+                snake.body.len() as u32 + could_eat_next_turn(rules.mode, board, snake) as u32;
+            for (i, b) in snake.body.iter().enumerate() {
+                if threatened.contains(b) {
+                    continue;
+                }
+                if let Some(cost) = cell_cost(b) {
+                    pathfinding_board.clear_after(&coord_to_pos(board, b), length - i as u32, cost);
                 }
-            }
-            if !found_body {
-                row_string += "1";
             }
         }
-        string_board.push(row_string);
     }
-    for r in &string_board {
-        println!("{}", r);
+    // a head to head cell is open again from the second step on
+    for c in &head_to_head {
+        if let Some(cost) = cell_cost(c) {
+            pathfinding_board.clear_after(&coord_to_pos(board, c), 2, cost);
+        }
+    }
+    pathfinding_board
+}
+
+// Whether there's food right next to the snake's head
+fn could_eat_next_turn(game_mode: GameMode, board: &BattlesnakeBoard, snake: &Battlesnake) -> bool {
+    MOVES.iter().any(|direction| {
+        engine::step(game_mode, board, &snake.head, direction)
+            .map(|c| board.food.contains(&c))
+            .unwrap_or(false)
+    })
+}
+
+// Builds a pathfinding board straight from a Battlesnake board, without going
+// through strings
+pub trait FromBattlesnake {
+    fn from_battlesnake(board: &BattlesnakeBoard) -> Self;
+}
+
+impl FromBattlesnake for PathfindingBoard {
+    // Every cell costs 1 and every body segment is a wall
+    fn from_battlesnake(board: &BattlesnakeBoard) -> PathfindingBoard {
+        let mut pathfinding_board =
+            PathfindingBoard::filled(board.width as u8, board.height as u8, 1, false);
+        for snake in &board.snakes {
+            for b in &snake.body {
+                pathfinding_board.set_cost(&coord_to_pos(board, b), None);
+            }
+        }
+        pathfinding_board
     }
-    let copy_of_board = string_board.clone();
-    let pathfinding_board = PathfindingBoard::new(string_board, false);
-    (pathfinding_board, copy_of_board)
 }
 
 pub fn coord_to_pos(board: &BattlesnakeBoard, c: &BattlesnakeCoord) -> PathfindingPos {
@@ -127,13 +294,30 @@ pub fn pos_to_coord(board: &BattlesnakeBoard, c: &PathfindingPos) -> Battlesnake
 #[cfg(test)]
 mod tests {
 
-    use rust_pathfinding::PathfindingPos;
+    use rust_pathfinding::{Board as PathfindingBoard, PathfindingPos};
+
+    use serde_json::json;
+    use std::collections::HashMap;
 
+    use crate::test_support::snake;
     use crate::{
-        utils::{build_pathfinding_board_with_hazards, coord_to_pos, pos_to_coord},
-        Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord,
+        engine::GameMode,
+        logic::view::BoardView,
+        utils::{
+            build_pathfinding_board_with_hazards, coord_to_pos, get_distance, get_hazard_cost,
+            get_next_move_from_coord, pos_to_coord, tail_moves, FromBattlesnake, MAX_HAZARD_COST,
+        },
+        Battlesnake, Board as BattlesnakeBoard, Coord as BattlesnakeCoord, Game,
     };
 
+    fn game() -> Game {
+        Game {
+            id: "game".to_string(),
+            ruleset: HashMap::new(),
+            timeout: 500,
+        }
+    }
+
     #[test]
     fn battlesnake_to_pathfinding() {
         let board = BattlesnakeBoard {
@@ -224,32 +408,12 @@ mod tests {
     fn headhunter_snake() {
         let snakes = vec![
             Battlesnake {
-                id: "gs_bcpvggkD9kYTTcmRGHRCCGXD".to_string(),
-                name: "Frostflayer".to_string(),
                 health: 100,
-                body: vec![
-                    BattlesnakeCoord { x: 2, y: 6 },
-                    BattlesnakeCoord { x: 1, y: 6 },
-                    BattlesnakeCoord { x: 1, y: 5 },
-                ],
-                head: BattlesnakeCoord { x: 2, y: 6 },
-                length: 3,
-                latency: "".to_string(),
-                shout: None,
+                ..snake("gs_bcpvggkD9kYTTcmRGHRCCGXD", vec![(2, 6), (1, 6), (1, 5)])
             },
             Battlesnake {
-                id: "gs_bcpvggkD9kYTTcmRGHRCCGXD".to_string(),
-                name: "Scared Bot".to_string(),
                 health: 98,
-                body: vec![
-                    BattlesnakeCoord { x: 9, y: 4 },
-                    BattlesnakeCoord { x: 9, y: 5 },
-                    BattlesnakeCoord { x: 8, y: 5 },
-                ],
-                head: BattlesnakeCoord { x: 9, y: 4 },
-                length: 3,
-                latency: "1".to_string(),
-                shout: Some("".to_string()),
+                ..snake("gs_bcpvggkD9kYTTcmRGHRCCGXD", vec![(9, 4), (9, 5), (8, 5)])
             },
         ];
         let board = BattlesnakeBoard {
@@ -257,13 +421,14 @@ mod tests {
             height: 11,
             food: Vec::new(),
             hazards: Vec::new(),
-            snakes: snakes,
+            snakes,
         };
-        let (_, board_string) = build_pathfinding_board_with_hazards(
+        let board_string = build_pathfinding_board_with_hazards(
             &crate::utils::SnakePersonality::HeadHunter,
-            &board,
-            &board.snakes[0],
-        );
+            &game(),
+            &BoardView::new(&board, &board.snakes[0]),
+        )
+        .to_lines();
 
         let expected_board = vec![
             "11111111111",
@@ -286,34 +451,18 @@ mod tests {
     fn headhunter_snake_duplicates() {
         let snakes = vec![
             Battlesnake {
-                id: "gs_bcpvggkD9kYTTcmRGHRCCGXD".to_string(),
-                name: "Frostflayer".to_string(),
                 health: 100,
-                body: vec![
-                    BattlesnakeCoord { x: 2, y: 6 },
-                    BattlesnakeCoord { x: 1, y: 6 },
-                    BattlesnakeCoord { x: 1, y: 6 },
-                    BattlesnakeCoord { x: 1, y: 5 },
-                ],
-                head: BattlesnakeCoord { x: 2, y: 6 },
-                length: 3,
-                latency: "".to_string(),
-                shout: None,
+                ..snake(
+                    "gs_bcpvggkD9kYTTcmRGHRCCGXD",
+                    vec![(2, 6), (1, 6), (1, 6), (1, 5)],
+                )
             },
             Battlesnake {
-                id: "gs_bcpvggkD9kYTTcmRGHRCCGXD".to_string(),
-                name: "Scared Bot".to_string(),
                 health: 98,
-                body: vec![
-                    BattlesnakeCoord { x: 9, y: 4 },
-                    BattlesnakeCoord { x: 9, y: 5 },
-                    BattlesnakeCoord { x: 9, y: 5 },
-                    BattlesnakeCoord { x: 8, y: 5 },
-                ],
-                head: BattlesnakeCoord { x: 9, y: 4 },
-                length: 3,
-                latency: "1".to_string(),
-                shout: Some("".to_string()),
+                ..snake(
+                    "gs_bcpvggkD9kYTTcmRGHRCCGXD",
+                    vec![(9, 4), (9, 5), (9, 5), (8, 5)],
+                )
             },
         ];
         let board = BattlesnakeBoard {
@@ -321,13 +470,14 @@ mod tests {
             height: 11,
             food: Vec::new(),
             hazards: Vec::new(),
-            snakes: snakes,
+            snakes,
         };
-        let (_, board_string) = build_pathfinding_board_with_hazards(
+        let board_string = build_pathfinding_board_with_hazards(
             &crate::utils::SnakePersonality::HeadHunter,
-            &board,
-            &board.snakes[0],
-        );
+            &game(),
+            &BoardView::new(&board, &board.snakes[0]),
+        )
+        .to_lines();
 
         let expected_board = vec![
             "11111111111",
@@ -345,4 +495,406 @@ mod tests {
 
         assert_eq!(board_string, expected_board);
     }
+
+    #[test]
+    fn timid_snake() {
+        let snakes = vec![
+            Battlesnake {
+                health: 100,
+                ..snake("gs_timid", vec![(2, 6), (1, 6), (1, 5)])
+            },
+            Battlesnake {
+                health: 98,
+                ..snake("gs_scary", vec![(9, 4), (9, 5), (8, 5)])
+            },
+        ];
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes,
+        };
+        let board_string = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::Timid,
+            &game(),
+            &BoardView::new(&board, &board.snakes[0]),
+        )
+        .to_lines();
+
+        let expected_board = vec![
+            "11111111111",
+            "11111111111",
+            "11111111111",
+            "11111111111",
+            "1XX11111111",
+            "1X111111XX1",
+            "11111111XXX",
+            "111111111X1",
+            "11111111111",
+            "11111111111",
+            "11111111111",
+        ];
+
+        assert_eq!(board_string, expected_board);
+    }
+
+    #[test]
+    fn hungry_snake() {
+        let snakes = vec![
+            Battlesnake {
+                health: 100,
+                ..snake("gs_hungry", vec![(2, 6), (1, 6), (1, 5)])
+            },
+            Battlesnake {
+                health: 100,
+                ..snake("gs_fed", vec![(9, 4), (9, 5), (8, 5), (8, 5)])
+            },
+        ];
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes,
+        };
+        let board_string = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::Hungry,
+            &game(),
+            &BoardView::new(&board, &board.snakes[0]),
+        )
+        .to_lines();
+
+        let expected_board = vec![
+            "11111111111",
+            "11111111111",
+            "11111111111",
+            "11111111111",
+            "1XX11111111",
+            "11111111XX1",
+            "111111111X1",
+            "11111111111",
+            "11111111111",
+            "11111111111",
+            "11111111111",
+        ];
+
+        assert_eq!(board_string, expected_board);
+    }
+
+    #[test]
+    fn hazard_costs() {
+        let mut you = Battlesnake {
+            health: 50,
+            ..snake("gs_you", vec![(5, 5), (5, 4), (5, 3)])
+        };
+        let mut board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: vec![BattlesnakeCoord { x: 2, y: 0 }],
+            hazards: vec![
+                BattlesnakeCoord { x: 0, y: 0 },
+                BattlesnakeCoord { x: 1, y: 0 },
+                BattlesnakeCoord { x: 1, y: 0 },
+                BattlesnakeCoord { x: 2, y: 0 },
+            ],
+            snakes: vec![you.clone()],
+        };
+        let board_string = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::HeadHunter,
+            &game(),
+            &BoardView::new(&board, &you),
+        )
+        .to_lines();
+        assert_eq!(board_string[10], "++111111111");
+        assert_eq!(
+            hazard_board_costs(&board, &you),
+            vec![Some(10), Some(19), Some(1)]
+        );
+
+        you.health = 20;
+        board.snakes = vec![you.clone()];
+        let board_string = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::HeadHunter,
+            &game(),
+            &BoardView::new(&board, &you),
+        )
+        .to_lines();
+        assert_eq!(board_string[10], "+X111111111");
+        assert_eq!(
+            hazard_board_costs(&board, &you),
+            vec![Some(23), None, Some(1)]
+        );
+    }
+
+    // The costs of the bottom left three cells, where `hazard_costs` stacks them
+    fn hazard_board_costs(board: &BattlesnakeBoard, you: &Battlesnake) -> Vec<Option<u32>> {
+        let pathfinding_board = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::HeadHunter,
+            &game(),
+            &BoardView::new(board, you),
+        );
+        (0..3)
+            .map(|x| pathfinding_board.cost(&coord_to_pos(board, &BattlesnakeCoord { x, y: 0 })))
+            .collect()
+    }
+
+    #[test]
+    fn stacked_hazards_cost_more() {
+        for health in [100, 60, 30] {
+            let costs: Vec<u32> = (1..)
+                .map_while(|stacked| get_hazard_cost(14, stacked, health))
+                .collect();
+            assert!(!costs.is_empty());
+            assert!(
+                costs.windows(2).all(|pair| pair[0] < pair[1]),
+                "{:?}",
+                costs
+            );
+            assert!(costs.iter().all(|cost| *cost <= MAX_HAZARD_COST));
+        }
+    }
+
+    #[test]
+    fn numeric_costs() {
+        let board = BattlesnakeBoard {
+            width: 4,
+            height: 3,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![Battlesnake {
+                health: 50,
+                ..snake("gs_you", vec![(1, 2), (1, 1), (2, 1)])
+            }],
+        };
+        let mut pathfinding_board = PathfindingBoard::from_battlesnake(&board);
+        assert_eq!(
+            pathfinding_board.to_lines(),
+            vec!["1X11".to_string(), "1XX1".to_string(), "1111".to_string()]
+        );
+
+        // costs past 9 work the same as small ones, they just can't be printed
+        let pos = coord_to_pos(&board, &BattlesnakeCoord { x: 3, y: 0 });
+        pathfinding_board.set_cost(&pos, Some(250));
+        assert_eq!(pathfinding_board.cost(&pos), Some(250));
+        assert_eq!(pathfinding_board.to_lines()[2], "111+");
+        let from = coord_to_pos(&board, &BattlesnakeCoord { x: 2, y: 0 });
+        assert!(pathfinding_board
+            .get_successors(&from)
+            .iter()
+            .any(|s| s.pos == pos && s.cost == 250));
+    }
+
+    #[test]
+    fn bodies_clear_over_time() {
+        let you = Battlesnake {
+            health: 50,
+            ..snake("gs_you", vec![(5, 5), (5, 4), (5, 3), (4, 3)])
+        };
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone()],
+        };
+        let pathfinding_board = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::Snacky,
+            &game(),
+            &BoardView::new(&board, &you),
+        );
+        let reaches = |from: (u32, u32), to: (u32, u32), elapsed: u32| {
+            let to = coord_to_pos(&board, &BattlesnakeCoord { x: to.0, y: to.1 });
+            pathfinding_board
+                .get_successors_at(
+                    &coord_to_pos(
+                        &board,
+                        &BattlesnakeCoord {
+                            x: from.0,
+                            y: from.1,
+                        },
+                    ),
+                    elapsed,
+                )
+                .iter()
+                .any(|s| s.pos == to)
+        };
+        // the tail is gone by the time we get there
+        assert!(reaches((4, 4), (4, 3), 0));
+        // the segment before it needs one more turn
+        assert!(!reaches((6, 3), (5, 3), 0));
+        assert!(reaches((6, 3), (5, 3), 1));
+        assert_eq!(pathfinding_board.last_clear(), 4);
+    }
+
+    #[test]
+    fn stacked_tails_stay_put() {
+        assert!(tail_moves(&snake("moving", vec![(5, 5), (5, 4), (5, 3)])));
+        assert!(!tail_moves(&snake("fed", vec![(5, 5), (5, 4), (5, 4)])));
+        assert!(!tail_moves(&snake("start", vec![(1, 1); 3])));
+        assert!(!tail_moves(&snake("stub", vec![(1, 1)])));
+    }
+
+    #[test]
+    fn head_to_head_cells_only_block_the_first_step() {
+        let you = snake("gs_you", vec![(1, 1), (0, 1), (0, 0)]);
+        // bigger than us and one step away from (2, 1)
+        let big = snake("big", vec![(3, 1), (3, 2), (3, 3), (3, 4)]);
+        let board = BattlesnakeBoard {
+            width: 5,
+            height: 5,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), big],
+        };
+        let pos = |x: u32, y: u32| coord_to_pos(&board, &BattlesnakeCoord { x, y });
+        let contested = pos(2, 1);
+        let reaches = |pathfinding_board: &PathfindingBoard, from, elapsed| {
+            pathfinding_board
+                .get_successors_at(&from, elapsed)
+                .iter()
+                .any(|s| s.pos == contested)
+        };
+
+        let snacky = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::Snacky,
+            &game(),
+            &BoardView::new(&board, &you),
+        );
+        // not straight from our head, but going round by (2, 2) or (2, 0) is fine
+        assert!(!reaches(&snacky, pos(1, 1), 0));
+        assert!(reaches(&snacky, pos(2, 2), 2));
+        assert!(reaches(&snacky, pos(2, 0), 2));
+
+        // timid snakes keep away from it the whole way
+        let timid = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::Timid,
+            &game(),
+            &BoardView::new(&board, &you),
+        );
+        assert!(!reaches(&timid, pos(2, 2), 2));
+    }
+
+    #[test]
+    fn bodies_next_to_food_clear_a_turn_later() {
+        let you = snake("gs_you", vec![(1, 1), (1, 0), (2, 0)]);
+        let enemy = snake("enemy", vec![(8, 5), (8, 4), (8, 3), (7, 3)]);
+        let mut board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone(), enemy],
+        };
+        // whether we could step from (6, 3) onto the enemy's tail at (7, 3)
+        let reaches_tail = |board: &BattlesnakeBoard, elapsed: u32| {
+            let pathfinding_board = build_pathfinding_board_with_hazards(
+                &crate::utils::SnakePersonality::Snacky,
+                &game(),
+                &BoardView::new(board, &you),
+            );
+            let tail = coord_to_pos(board, &BattlesnakeCoord { x: 7, y: 3 });
+            pathfinding_board
+                .get_successors_at(
+                    &coord_to_pos(board, &BattlesnakeCoord { x: 6, y: 3 }),
+                    elapsed,
+                )
+                .iter()
+                .any(|s| s.pos == tail)
+        };
+        assert!(reaches_tail(&board, 0));
+
+        // with food in front of it the enemy might grow, and the tail stays put
+        board.food.push(BattlesnakeCoord { x: 8, y: 6 });
+        assert!(!reaches_tail(&board, 0));
+        assert!(reaches_tail(&board, 1));
+    }
+
+    #[test]
+    fn constrictor_bodies_never_clear() {
+        let you = Battlesnake {
+            health: 100,
+            ..snake("gs_you", vec![(5, 5), (5, 4), (5, 3), (4, 3)])
+        };
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: vec![you.clone()],
+        };
+        let game = Game {
+            ruleset: HashMap::from([("name".to_string(), json!("constrictor"))]),
+            ..game()
+        };
+        // Hungry would normally count on the tail moving out of the way
+        let pathfinding_board = build_pathfinding_board_with_hazards(
+            &crate::utils::SnakePersonality::Hungry,
+            &game,
+            &BoardView::new(&board, &you),
+        );
+        for segment in &you.body {
+            assert_eq!(pathfinding_board.cost(&coord_to_pos(&board, segment)), None);
+        }
+        assert_eq!(pathfinding_board.last_clear(), 0);
+        // however long we take, the tail stays a wall
+        let tail = coord_to_pos(&board, &BattlesnakeCoord { x: 4, y: 3 });
+        let beside_tail = coord_to_pos(&board, &BattlesnakeCoord { x: 3, y: 3 });
+        assert!(!pathfinding_board
+            .get_successors_at(&beside_tail, 100)
+            .iter()
+            .any(|s| s.pos == tail));
+    }
+
+    #[test]
+    fn wrapped_moves_and_distances() {
+        let board = BattlesnakeBoard {
+            width: 11,
+            height: 11,
+            food: Vec::new(),
+            hazards: Vec::new(),
+            snakes: Vec::new(),
+        };
+        let edge = BattlesnakeCoord { x: 10, y: 0 };
+        assert_eq!(
+            get_next_move_from_coord(&edge, &BattlesnakeCoord { x: 0, y: 0 }),
+            "right"
+        );
+        assert_eq!(
+            get_next_move_from_coord(&edge, &BattlesnakeCoord { x: 10, y: 10 }),
+            "down"
+        );
+        assert_eq!(
+            get_next_move_from_coord(
+                &BattlesnakeCoord { x: 0, y: 10 },
+                &BattlesnakeCoord { x: 10, y: 10 }
+            ),
+            "left"
+        );
+        assert_eq!(
+            get_next_move_from_coord(
+                &BattlesnakeCoord { x: 3, y: 10 },
+                &BattlesnakeCoord { x: 3, y: 0 }
+            ),
+            "up"
+        );
+        assert_eq!(
+            get_distance(
+                GameMode::Wrapped,
+                &board,
+                &edge,
+                &BattlesnakeCoord { x: 0, y: 0 }
+            ),
+            1.0
+        );
+        assert_eq!(
+            get_distance(
+                GameMode::Standard,
+                &board,
+                &edge,
+                &BattlesnakeCoord { x: 0, y: 0 }
+            ),
+            10.0
+        );
+    }
 }